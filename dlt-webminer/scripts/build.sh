@@ -5,20 +5,37 @@ SCRIPT_DIR="$(cd "$(dirname "$0")" && pwd)"
 PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
 WASM_DIR="$PROJECT_DIR/wasm-miner"
 WEB_DIR="$PROJECT_DIR/web"
+# The site serves its own copy of the miner
+PUBLIC_DIR="$(dirname "$PROJECT_DIR")/public/miner"
 
 echo "[*] Building WASM miner..."
 cd "$WASM_DIR"
 
-# Build with wasm-pack targeting web (ES modules, no bundler needed)
-wasm-pack build --target web --release --out-dir "$WASM_DIR/pkg"
+# Build with wasm-pack targeting web (ES modules, no bundler needed). The
+# cdylib is the wasm/ crate; --out-name keeps the dlt_webminer file names.
+if [ "${THREADS:-0}" = "1" ]; then
+    # MinerPool: shared memory needs std rebuilt with atomics (nightly).
+    # RUSTFLAGS replaces .cargo/config.toml, so repeat its features.
+    echo "[*] threads build (shared memory + atomics)"
+    RUSTFLAGS="-C target-feature=+simd128,+bulk-memory,+sign-ext,+atomics,+mutable-globals" \
+        rustup run nightly wasm-pack build "$WASM_DIR/wasm" --target web --release \
+        --out-dir "$WASM_DIR/pkg" --out-name dlt_webminer \
+        -- --features threads -Z build-std=panic_abort,std
+else
+    wasm-pack build "$WASM_DIR/wasm" --target web --release --out-dir "$WASM_DIR/pkg" --out-name dlt_webminer
+fi
 
-echo "[*] Copying WASM artifacts to web directory..."
-cp "$WASM_DIR/pkg/dlt_webminer_bg.wasm" "$WEB_DIR/dlt_webminer_bg.wasm"
-cp "$WASM_DIR/pkg/dlt_webminer.js" "$WEB_DIR/dlt_webminer.js"
+echo "[*] Copying WASM artifacts to web and public/miner..."
+for dir in "$WEB_DIR" "$PUBLIC_DIR"; do
+    cp "$WASM_DIR/pkg/dlt_webminer_bg.wasm" "$dir/dlt_webminer_bg.wasm"
+    cp "$WASM_DIR/pkg/dlt_webminer.js" "$dir/dlt_webminer.js"
+    # MinerPool's worker script lives in web/
+    [ "$dir" = "$WEB_DIR" ] || cp "$WEB_DIR/pool-worker.js" "$dir/pool-worker.js"
+done
 
 echo "[+] Build complete!"
-echo "    WASM: $WEB_DIR/dlt_webminer_bg.wasm"
-echo "    JS:   $WEB_DIR/dlt_webminer.js"
+echo "    WASM: $WEB_DIR/dlt_webminer_bg.wasm, $PUBLIC_DIR/dlt_webminer_bg.wasm"
+echo "    JS:   $WEB_DIR/dlt_webminer.js, $PUBLIC_DIR/dlt_webminer.js"
 echo ""
 echo "To serve locally with required headers:"
 echo "    cd $WEB_DIR && python3 -c \""
@@ -0,0 +1,26 @@
+#!/bin/bash
+set -euo pipefail
+
+SCRIPT_DIR="$(cd "$(dirname "$0")" && pwd)"
+PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
+WASM_DIR="$PROJECT_DIR/wasm-miner"
+
+cd "$WASM_DIR"
+
+echo "[*] Lints and tests (default features, native miner)..."
+cargo clippy --workspace --all-targets -- -D warnings
+cargo clippy --workspace --all-targets --features native-miner -- -D warnings
+cargo test --workspace --features native-miner
+
+# The core must keep building without std: hashing and verification with
+# no allocator at all, and mining/templates with only `alloc`.
+echo "[*] no_std builds..."
+cargo clippy --lib --no-default-features -- -D warnings
+cargo clippy --lib --no-default-features --features alloc -- -D warnings
+cargo test --lib --no-default-features
+cargo test --lib --no-default-features --features alloc
+
+echo "[*] WASM build..."
+cargo clippy --workspace --target wasm32-unknown-unknown -- -D warnings
+
+echo "[+] All checks passed"
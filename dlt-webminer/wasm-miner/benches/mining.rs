@@ -0,0 +1,69 @@
+//! Hashrate benchmarks for the mining hot path.
+//!
+//! No external harness: each case runs a fixed number of nonces a few times
+//! and reports the best MH/s. Native: `cargo bench`. For the WASM numbers that
+//! matter to browser miners, build for `wasm32-wasip1` with `+simd128` and run
+//! the bench binary under a WASI runtime.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use dlt_webminer::hasher::Backend;
+use dlt_webminer::mining::MiningJob;
+use dlt_webminer::sha256::{compute_midstate, meets_difficulty_u32, HashJob};
+use dlt_webminer::utils::write_i64;
+
+const NONCES: u32 = 1_000_000;
+const RUNS: usize = 5;
+
+fn bench(name: &str, mut f: impl FnMut(i64)) {
+    let mut best = f64::MAX;
+    // One extra warm-up run; JIT-compiled WASM only tiers up on re-entry
+    for run in 0..=RUNS {
+        let start = Instant::now();
+        f(1_000_000 + run as i64 * NONCES as i64);
+        if run > 0 {
+            best = best.min(start.elapsed().as_secs_f64());
+        }
+    }
+    println!("{:<40} {:>8.2} MH/s", name, NONCES as f64 / best / 1e6);
+}
+
+fn main() {
+    // Short tail (one final block) and long tail (padding spills into a
+    // second block, most of the first block is nonce-invariant).
+    let short = b"6001173836800012ab34cd56ef7890ab12cd34ef567890abcdef0123456789abcdef00000abc".to_vec();
+    let mut long = short.clone();
+    long.extend_from_slice(b"0123456789abcdef0123456789abcdef0123456789");
+
+    for (label, prefix) in [("short tail", &short), ("long tail", &long)] {
+        let (state, tail) = compute_midstate(prefix);
+
+        // Impossible difficulty: every nonce is hashed and rejected
+        for backend in Backend::available() {
+            let mut job = MiningJob::from_midstate(state.h, state.len, &tail, b"6", 256);
+            job.set_backend(backend);
+            bench(&format!("mine, {} ({})", backend.name(), label), |start| {
+                black_box(job.mine(start, 1, NONCES));
+            });
+        }
+
+        // Full hash + full check vs the leading-word early exit
+        let job = HashJob::new(state.h, state.len, &tail, 7, b"6");
+        bench(&format!("HashJob::hash + check ({})", label), |start| {
+            let mut buf = [0u8; 20];
+            for n in start..start + NONCES as i64 {
+                let len = write_i64(&mut buf, n);
+                let state = job.hash(&buf[..len]);
+                black_box(meets_difficulty_u32(&state, 24));
+            }
+        });
+        bench(&format!("HashJob::check ({})", label), |start| {
+            let mut buf = [0u8; 20];
+            for n in start..start + NONCES as i64 {
+                let len = write_i64(&mut buf, n);
+                black_box(job.check(&buf[..len], 24));
+            }
+        });
+    }
+}
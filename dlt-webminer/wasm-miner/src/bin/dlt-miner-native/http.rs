@@ -0,0 +1,134 @@
+//! Just enough HTTP/1.1 for the node API: one request per connection,
+//! Content-Length, chunked or read-to-close bodies. Plain `http://` only.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A parsed `http://host[:port][/path]` base URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BaseUrl {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl BaseUrl {
+    pub fn parse(url: &str) -> Result<Self, String> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("{}: only http:// node URLs are supported", url))?;
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| format!("{}: bad port", url))?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(format!("{}: missing host", url));
+        }
+        Ok(BaseUrl { host: host.to_string(), port, path: path.trim_end_matches('/').to_string() })
+    }
+
+    pub fn get(&self, path: &str) -> Result<String, String> {
+        self.request("GET", path, None)
+    }
+
+    pub fn post_json(&self, path: &str, body: &str) -> Result<String, String> {
+        self.request("POST", path, Some(body))
+    }
+
+    /// Send one request and return the response body, whatever the status:
+    /// the node reports failures in its JSON.
+    fn request(&self, method: &str, path: &str, body: Option<&str>) -> Result<String, String> {
+        let fail = |e: std::io::Error| format!("{} {}{}: {}", method, self.path, path, e);
+
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).map_err(fail)?;
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(fail)?;
+        stream.set_write_timeout(Some(TIMEOUT)).map_err(fail)?;
+
+        let mut request = format!(
+            "{} {}{} HTTP/1.1\r\nHost: {}:{}\r\nAccept: application/json\r\nConnection: close\r\n",
+            method, self.path, path, self.host, self.port
+        );
+        if let Some(body) = body {
+            request.push_str(&format!("Content-Type: application/json\r\nContent-Length: {}\r\n", body.len()));
+        }
+        request.push_str("\r\n");
+        request.push_str(body.unwrap_or(""));
+        stream.write_all(request.as_bytes()).map_err(fail)?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).map_err(fail)?;
+        parse_response(&response)
+    }
+}
+
+/// Body of a raw HTTP/1.1 response.
+fn parse_response(response: &[u8]) -> Result<String, String> {
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or("truncated HTTP response")?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let mut body = &response[split + 4..];
+
+    let mut chunked = false;
+    for line in head.lines().skip(1) {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") && value.eq_ignore_ascii_case("chunked") {
+            chunked = true;
+        } else if name.eq_ignore_ascii_case("content-length") {
+            let len: usize = value.parse().map_err(|_| "bad Content-Length")?;
+            body = body.get(..len).ok_or("truncated HTTP body")?;
+        }
+    }
+
+    let body = if chunked { dechunk(body)? } else { body.to_vec() };
+    String::from_utf8(body).map_err(|_| "response body isn't UTF-8".to_string())
+}
+
+fn dechunk(mut data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    loop {
+        let line_end = data.windows(2).position(|w| w == b"\r\n").ok_or("truncated chunk")?;
+        let size_line = String::from_utf8_lossy(&data[..line_end]);
+        // Chunk extensions (";name=value") aren't used by the node
+        let size_hex = size_line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_hex, 16).map_err(|_| "bad chunk size")?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Ok(out);
+        }
+        out.extend_from_slice(data.get(..size).ok_or("truncated chunk")?);
+        data = data.get(size + 2..).ok_or("truncated chunk")?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let url = BaseUrl::parse("http://127.0.0.1:8001/").unwrap();
+        assert_eq!(url, BaseUrl { host: "127.0.0.1".into(), port: 8001, path: String::new() });
+        let url = BaseUrl::parse("http://node.example/api").unwrap();
+        assert_eq!((url.port, url.path.as_str()), (80, "/api"));
+        assert!(BaseUrl::parse("https://node.example").is_err());
+        assert!(BaseUrl::parse("http://:80").is_err());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let plain = b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\n{}xxjunk";
+        assert_eq!(parse_response(plain).unwrap(), "{}xx");
+        let chunked = b"HTTP/1.1 400 Bad Request\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n{\"a\":\r\n2\r\n1}\r\n0\r\n\r\n";
+        assert_eq!(parse_response(chunked).unwrap(), "{\"a\":1}");
+        let until_close = b"HTTP/1.0 200 OK\r\n\r\n[1,2]";
+        assert_eq!(parse_response(until_close).unwrap(), "[1,2]");
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\n").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! Native solo miner: the browser miner's mining code on std threads.
+//!
+//! Polls a node's `/status`, builds the next block from `/mempool` the way
+//! web/work-manager.js does, mines it with `pool::MinerPool` over strided
+//! nonces, and posts solutions to `/block/submit`.
+//!
+//! Built with `cargo build --release --features native-miner`.
+//!
+//! ```text
+//! dlt-miner-native --node http://127.0.0.1:8001 --address <addr> [--threads N] [--poll-secs S]
+//! ```
+
+mod http;
+mod node;
+
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use dlt_webminer::pool::MinerPool;
+use dlt_webminer::selftest;
+
+use http::BaseUrl;
+use node::{Status, Work};
+
+const USAGE: &str = "usage: dlt-miner-native --node http://host:port --address ADDR [--threads N] [--poll-secs S]";
+
+struct Args {
+    node: BaseUrl,
+    address: String,
+    threads: usize,
+    poll: Duration,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let (mut node, mut address) = (None, None);
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut poll = Duration::from_secs(3);
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", flag));
+        match flag.as_str() {
+            "--node" => node = Some(BaseUrl::parse(&value()?)?),
+            "--address" => address = Some(value()?),
+            "--threads" => threads = value()?.parse().map_err(|_| "--threads must be a number")?,
+            "--poll-secs" => poll = Duration::from_secs(value()?.parse().map_err(|_| "--poll-secs must be a number")?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ => return Err(format!("unknown argument {}\n{}", flag, USAGE)),
+        }
+    }
+    Ok(Args {
+        node: node.ok_or(USAGE)?,
+        address: address.ok_or(USAGE)?,
+        threads: threads.max(1),
+        poll,
+    })
+}
+
+fn main() -> ExitCode {
+    if let Err(e) = selftest::self_test() {
+        eprintln!("[!] {}; refusing to mine", e);
+        return ExitCode::FAILURE;
+    }
+    match parse_args(std::env::args().skip(1)) {
+        Ok(args) => run(args),
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Args) -> ! {
+    println!("[*] Mining to {} on {} threads", args.address, args.threads);
+    let mut pool = MinerPool::new();
+    // Tip the current work was built on, and its pool generation
+    let mut tip: Option<Status> = None;
+    let mut current: Option<(u32, Work)> = None;
+    let mut last_poll: Option<Instant> = None;
+    let (mut report_at, mut report_hashes) = (Instant::now(), 0);
+
+    loop {
+        if last_poll.is_none_or(|at| at.elapsed() >= args.poll) {
+            last_poll = Some(Instant::now());
+            match node::fetch_status(&args.node) {
+                Ok(status) if tip.as_ref() != Some(&status) => {
+                    let (now, nanos) = unix_now();
+                    let work = Work::new(&status, node::fetch_mempool(&args.node), &args.address, now, nanos);
+                    println!(
+                        "[*] Mining block #{} | {} bits | {} txs",
+                        work.template.index,
+                        work.template.diff_bits(),
+                        work.txs.len()
+                    );
+                    let job = work.template.mining_job().expect("mainnet's suffix is just the difficulty");
+                    let generation = pool.start(&job, args.threads, |thread| {
+                        thread::spawn(thread);
+                    });
+                    current = Some((generation, work));
+                    tip = Some(status);
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("[!] Status: {}", e),
+            }
+        }
+
+        let status = pool.poll();
+        for solution in status.solutions {
+            let Some((_, work)) = current.as_ref().filter(|(generation, _)| *generation == solution.generation) else {
+                continue;
+            };
+            println!("[+] Block #{} solved: nonce {} hash {}", work.template.index, solution.result.nonce, solution.result.hash_hex);
+            let block = work.block(&solution.result);
+            if let Err(errors) = work.validate(&block) {
+                // The node would only reject it; don't send it
+                let reasons: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                eprintln!("[!] Block #{} invalid, not submitting: {}", work.template.index, reasons.join("; "));
+            } else {
+                match node::submit_block(&args.node, &block.to_json()) {
+                    Ok(()) => println!("[+] Block #{} accepted", work.template.index),
+                    Err(e) => eprintln!("[!] Block #{} rejected: {}", work.template.index, e),
+                }
+            }
+            // The job retired with the solution; rebuild from a fresh poll
+            tip = None;
+            last_poll = None;
+        }
+
+        if report_at.elapsed() >= Duration::from_secs(10) {
+            let rate = (status.hashes - report_hashes) as f64 / report_at.elapsed().as_secs_f64();
+            println!("[*] {:.2} MH/s", rate / 1e6);
+            (report_at, report_hashes) = (Instant::now(), status.hashes);
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Unix time in seconds, and in nanoseconds for the coinbase signature.
+fn unix_now() -> (i64, i64) {
+    let since = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    (since.as_secs() as i64, since.as_nanos() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Result<Args, String> {
+        parse_args(list.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&["--node", "http://127.0.0.1:8001", "--address", "dlt1abc", "--threads", "3"]).unwrap();
+        assert_eq!((parsed.address.as_str(), parsed.threads), ("dlt1abc", 3));
+        assert_eq!(parsed.poll, Duration::from_secs(3));
+        assert!(args(&["--node", "http://127.0.0.1:8001"]).is_err());
+        assert!(args(&["--node", "https://x", "--address", "a"]).is_err());
+        assert!(args(&["--threads"]).is_err());
+        assert!(args(&["--bogus"]).is_err());
+    }
+}
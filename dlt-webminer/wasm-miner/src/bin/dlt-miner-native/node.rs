@@ -0,0 +1,184 @@
+//! Node JSON API and block assembly, as in web/work-manager.js and
+//! web/tx-builder.js.
+//!
+//! Every response is `{ success, message, data }`. Transactions and blocks
+//! are written in Go's json.Marshal field order with its omitempty rules,
+//! since the Merkle root and pre-fork block hashes are taken over exactly
+//! those bytes.
+
+use serde_json::Value;
+
+use dlt_webminer::block::Block;
+use dlt_webminer::consensus::{validate_block, BlockError, ConsensusParams, PrevHeader};
+use dlt_webminer::merkle::transaction_merkle_root;
+use dlt_webminer::mining::MiningResult;
+use dlt_webminer::template::{BlockTemplate, MERKLE_ROOT_FORK_HEIGHT};
+use dlt_webminer::transaction::{transactions_json, Transaction};
+
+use crate::http::BaseUrl;
+
+/// Chain tip from `/status`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Status {
+    /// Also the index of the next block.
+    pub height: i64,
+    pub difficulty: i32,
+    pub difficulty_bits: u32,
+    pub last_block_hash: String,
+}
+
+/// The `data` of a `{ success, message, data }` response.
+fn unwrap_response(body: &str) -> Result<Value, String> {
+    let mut response: Value = serde_json::from_str(body).map_err(|e| format!("bad JSON from node: {}", e))?;
+    if response["success"] != Value::Bool(true) {
+        return Err(response["message"].as_str().unwrap_or("request failed").to_string());
+    }
+    Ok(response["data"].take())
+}
+
+pub fn fetch_status(node: &BaseUrl) -> Result<Status, String> {
+    let data = unwrap_response(&node.get("/status")?)?;
+    let field = |name: &str| data[name].as_i64().ok_or_else(|| format!("/status: missing {}", name));
+    Ok(Status {
+        height: field("blockchain_height")?,
+        difficulty: field("difficulty")? as i32,
+        difficulty_bits: data["difficulty_bits"].as_u64().unwrap_or(0) as u32,
+        last_block_hash: data["last_block_hash"].as_str().ok_or("/status: missing last_block_hash")?.to_string(),
+    })
+}
+
+/// Pending transactions; an unreachable or empty mempool is no
+/// transactions, as in the web miner.
+pub fn fetch_mempool(node: &BaseUrl) -> Vec<Transaction> {
+    node.get("/mempool")
+        .and_then(|body| unwrap_response(&body))
+        .ok()
+        .and_then(|data| serde_json::from_value(data["transactions"].clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Post a solved block. Err carries the node's rejection message.
+pub fn submit_block(node: &BaseUrl, block_json: &str) -> Result<(), String> {
+    unwrap_response(&node.post_json("/block/submit", block_json)?).map(|_| ())
+}
+
+/// A block being mined: the hashed header plus what else goes on the wire.
+pub struct Work {
+    pub template: BlockTemplate,
+    pub txs: Vec<Transaction>,
+    pub merkle_root: String,
+}
+
+impl Work {
+    /// Next block on `status` paying `address`: coinbase first, then the
+    /// mempool. `now` is unix seconds; `nanos` makes the coinbase
+    /// signature unique. Mempool transactions that would make the block
+    /// fail `validate` are left out: other coinbases, fees below the
+    /// minimum, and fees that would overflow the coinbase amount.
+    pub fn new(status: &Status, mempool: Vec<Transaction>, address: &str, now: i64, nanos: i64) -> Self {
+        let params = ConsensusParams::MAINNET;
+        let index = status.height;
+        let mut amount = params.block_reward(index);
+        let mempool: Vec<Transaction> = mempool
+            .into_iter()
+            .filter(|tx| !tx.is_coinbase() && tx.fee >= params.min_fee)
+            .filter(|tx| match amount.checked_add(tx.fee) {
+                Some(total) => {
+                    amount = total;
+                    true
+                }
+                None => false,
+            })
+            .collect();
+        let coinbase = Transaction {
+            from: "SYSTEM".to_string(),
+            to: address.to_string(),
+            amount,
+            timestamp: now,
+            signature: format!("coinbase-{}-{}", index, nanos),
+            ..Transaction::default()
+        };
+        let txs: Vec<Transaction> = std::iter::once(coinbase).chain(mempool).collect();
+
+        let merkle_root = transaction_merkle_root(&txs);
+        let tx_data = if index >= MERKLE_ROOT_FORK_HEIGHT { merkle_root.clone() } else { transactions_json(&txs) };
+
+        let template = BlockTemplate::new(
+            index,
+            now,
+            &tx_data,
+            &status.last_block_hash,
+            status.difficulty,
+            status.difficulty_bits,
+        );
+        Work { template, txs, merkle_root }
+    }
+
+    /// The solved block, ready for `/block/submit` once `to_json`'d.
+    pub fn block(&self, solution: &MiningResult) -> Block {
+        let t = &self.template;
+        Block {
+            index: t.index,
+            timestamp: solution.timestamp.unwrap_or(t.timestamp),
+            transactions: self.txs.clone(),
+            merkle_root: self.merkle_root.clone(),
+            previous_hash: t.previous_hash.clone(),
+            hash: solution.hash_hex.clone(),
+            nonce: solution.nonce,
+            difficulty: t.difficulty,
+            difficulty_bits: t.difficulty_bits,
+        }
+    }
+
+    /// Check a solved block against consensus rules before submitting it.
+    pub fn validate(&self, block: &Block) -> Result<(), Vec<BlockError>> {
+        let prev = PrevHeader { index: self.template.index - 1, hash: &self.template.previous_hash };
+        validate_block(block, &prev, &ConsensusParams::MAINNET)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_work_and_block_json() {
+        let status = Status { height: 6001, difficulty: 6, difficulty_bits: 10, last_block_hash: "00ab".into() };
+        let mempool = vec![Transaction { from: "x".into(), to: "y".into(), amount: 1, fee: 20_000, ..Transaction::default() }];
+        let work = Work::new(&status, mempool, "miner", 1700000000, 42);
+
+        assert_eq!(work.txs[0].amount, 5_000_000_000 + 20_000);
+        assert_eq!(work.txs[0].signature, "coinbase-6001-42");
+        assert_eq!(work.template.tx_data, work.merkle_root);
+
+        let solution = work.template.mining_job().unwrap().mine(0, 1, 50_000).expect("solution at 10 bits");
+        let json = work.block(&solution).to_json();
+        let block: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(block["Hash"], work.template.finalize_hex(solution.nonce));
+        assert_eq!(block["Nonce"], solution.nonce);
+        assert_eq!(block["DifficultyBits"], 10);
+        assert!(json.starts_with(r#"{"Index":6001,"Timestamp":1700000000,"transactions":[{"from":"SYSTEM""#));
+        let parsed = Block::from_json(&json).unwrap();
+        assert_eq!(parsed.calculate_hash(), parsed.hash);
+        assert_eq!(parsed.transactions, work.txs);
+        assert_eq!(work.validate(&parsed), Ok(()));
+
+        // A fee the node's mempool shouldn't hold, but mustn't overflow on
+        let greedy = Transaction { fee: i64::MAX, ..work.txs[1].clone() };
+        let capped = Work::new(&status, vec![greedy, work.txs[1].clone()], "miner", 1700000000, 42);
+        assert_eq!(capped.txs.len(), 2);
+        assert_eq!(capped.txs[0].amount, 5_000_000_000 + 20_000);
+
+        // Nor anything else that would fail validation and be re-mined forever
+        let cheap = Transaction { fee: 9_999, ..work.txs[1].clone() };
+        let stray_coinbase = Transaction { from: "SYSTEM".into(), ..work.txs[1].clone() };
+        let filtered = Work::new(&status, vec![cheap, stray_coinbase, work.txs[1].clone()], "miner", 1700000000, 42);
+        assert_eq!(filtered.txs[1..], work.txs[1..]);
+        let solution = filtered.template.mining_job().unwrap().mine(0, 1, 50_000).expect("solution at 10 bits");
+        assert_eq!(filtered.validate(&filtered.block(&solution)), Ok(()));
+
+        // Before the fork the header hashes the transaction JSON itself
+        let old = Work::new(&Status { height: 10, ..status }, Vec::new(), "miner", 1700000000, 42);
+        assert_eq!(old.template.tx_data, format!("[{}]", old.txs[0].to_json()));
+    }
+}
@@ -0,0 +1,239 @@
+//! Blocks as the node sends them and `/block/submit` expects them.
+//! Ported from dilithiumcoin's Block struct, marshalled by Go as:
+//!
+//!   Index, Timestamp, transactions, MerkleRoot (omitempty), PreviousHash,
+//!   Hash, Nonce, Difficulty, DifficultyBits (omitempty)
+//!
+//! `calculate_hash` goes through `BlockTemplate`, so a parsed block hashes
+//! with the same layout the miner used to find it.
+
+use core::fmt::Write;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::json::{ParseJsonError, Reader};
+use crate::template::{BlockTemplate, MERKLE_ROOT_FORK_HEIGHT};
+use crate::transaction::{transactions_json, write_go_string, Transaction};
+
+/// A full block: header, transactions and the solution.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Block {
+    pub index: i64,
+    pub timestamp: i64,
+    pub transactions: Vec<Transaction>,
+    /// Merkle root hex, empty on blocks that predate it.
+    pub merkle_root: String,
+    pub previous_hash: String,
+    pub hash: String,
+    pub nonce: i64,
+    pub difficulty: i32,
+    /// Required leading zero bits, or 0 on blocks that predate them.
+    pub difficulty_bits: u32,
+}
+
+impl Block {
+    /// Append the JSON exactly as Go's json.Marshal writes it.
+    pub fn write_json(&self, out: &mut String) {
+        let _ = write!(out, "{{\"Index\":{},\"Timestamp\":{},\"transactions\":", self.index, self.timestamp);
+        out.push_str(&transactions_json(&self.transactions));
+        if !self.merkle_root.is_empty() {
+            out.push_str(",\"MerkleRoot\":");
+            write_go_string(out, &self.merkle_root);
+        }
+        out.push_str(",\"PreviousHash\":");
+        write_go_string(out, &self.previous_hash);
+        out.push_str(",\"Hash\":");
+        write_go_string(out, &self.hash);
+        let _ = write!(out, ",\"Nonce\":{},\"Difficulty\":{}", self.nonce, self.difficulty);
+        if self.difficulty_bits != 0 {
+            let _ = write!(out, ",\"DifficultyBits\":{}", self.difficulty_bits);
+        }
+        out.push('}');
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    /// Parse a block as the node sends it, with Go's leniency: omitted
+    /// fields are zero, unknown ones are ignored, `null` transactions are
+    /// none.
+    pub fn from_json(json: &str) -> Result<Self, ParseJsonError> {
+        let mut r = Reader::new(json);
+        let mut block = Block::default();
+        r.object(|r, key| {
+            match key.to_ascii_lowercase().as_str() {
+                "index" => block.index = r.int("Index")?,
+                "timestamp" => block.timestamp = r.int("Timestamp")?,
+                "transactions" => {
+                    block.transactions.clear();
+                    r.array(|r| {
+                        block.transactions.push(Transaction::read_json(r)?);
+                        Ok(())
+                    })?;
+                }
+                "merkleroot" => block.merkle_root = r.string("MerkleRoot")?,
+                "previoushash" => block.previous_hash = r.string("PreviousHash")?,
+                "hash" => block.hash = r.string("Hash")?,
+                "nonce" => block.nonce = r.int("Nonce")?,
+                "difficulty" => {
+                    block.difficulty = r.int("Difficulty")?.try_into().map_err(|_| ParseJsonError::Field("Difficulty"))?
+                }
+                "difficultybits" => {
+                    block.difficulty_bits =
+                        r.int("DifficultyBits")?.try_into().map_err(|_| ParseJsonError::Field("DifficultyBits"))?
+                }
+                _ => r.skip()?,
+            }
+            Ok(())
+        })?;
+        r.end()?;
+        Ok(block)
+    }
+
+    /// What the hash commits to for the transactions: the Merkle root from
+    /// `MERKLE_ROOT_FORK_HEIGHT` on, their JSON array before it.
+    pub fn tx_data(&self) -> String {
+        if self.index >= MERKLE_ROOT_FORK_HEIGHT {
+            self.merkle_root.clone()
+        } else {
+            transactions_json(&self.transactions)
+        }
+    }
+
+    /// The header without its nonce, as a mainnet template.
+    pub fn template(&self) -> BlockTemplate {
+        BlockTemplate::new(
+            self.index,
+            self.timestamp,
+            &self.tx_data(),
+            &self.previous_hash,
+            self.difficulty,
+            self.difficulty_bits,
+        )
+    }
+
+    /// The exact bytes the block hash is SHA-256 of: index, timestamp,
+    /// `tx_data()`, previous hash, nonce and difficulty, in decimal where
+    /// they're integers. Picks the encoding by height, so any block from
+    /// genesis on can be checked.
+    pub fn hash_preimage(&self) -> Vec<u8> {
+        self.template().preimage(self.nonce)
+    }
+
+    /// The hash of this header and nonce, in hex (Go's CalculateHash). A
+    /// well-formed block's `hash` equals it.
+    pub fn calculate_hash(&self) -> String {
+        self.template().finalize_hex(self.nonce)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::transaction_merkle_root;
+    use crate::sha256::sha256;
+    use crate::utils::hash_to_hex;
+
+    const GENESIS_JSON: &str = r#"{"Index":0,"Timestamp":1738368000,"transactions":[],"PreviousHash":"0","Hash":"0000002835112676fbe3d7588fa08557751aa4045cc8575f16037247350815ae","Nonce":5892535,"Difficulty":6}"#;
+
+    fn post_fork() -> Block {
+        let transactions = vec![
+            Transaction {
+                from: "SYSTEM".into(),
+                to: "miner".into(),
+                amount: 5_000_010_000,
+                timestamp: 1738400000,
+                signature: "coinbase-6001-42".into(),
+                ..Transaction::default()
+            },
+            Transaction {
+                from: "a<b".into(),
+                to: "c&d".into(),
+                amount: 1,
+                fee: 10_000,
+                data: "memo\n".into(),
+                timestamp: 1738399999,
+                signature: "sig".into(),
+                public_key: "pk".into(),
+            },
+        ];
+        let mut block = Block {
+            index: 6001,
+            timestamp: 1738400000,
+            merkle_root: transaction_merkle_root(&transactions),
+            transactions,
+            previous_hash: "00000014a2f0c6b5e2d3f1a8c7b6e5d4c3b2a1908f7e6d5c4b3a29180f7e6d5c".into(),
+            nonce: -7_000_000_000_000,
+            difficulty: 6,
+            difficulty_bits: 24,
+            ..Block::default()
+        };
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    #[test]
+    fn test_genesis_round_trips_and_hashes() {
+        let genesis = Block::from_json(GENESIS_JSON).unwrap();
+        assert_eq!(genesis.nonce, 5892535);
+        assert_eq!(genesis.tx_data(), "[]");
+        assert_eq!(genesis.calculate_hash(), genesis.hash);
+        assert_eq!(genesis.to_json(), GENESIS_JSON);
+    }
+
+    #[test]
+    fn test_post_fork_round_trip() {
+        let block = post_fork();
+        assert_eq!(block.tx_data(), block.merkle_root);
+        let json = block.to_json();
+        assert!(json.starts_with(r#"{"Index":6001,"Timestamp":1738400000,"transactions":[{"from":"SYSTEM""#));
+        assert!(json.ends_with(r#","Nonce":-7000000000000,"Difficulty":6,"DifficultyBits":24}"#));
+        assert!(json.contains(r#""signature":"sig","public_key":"pk"}],"MerkleRoot":""#));
+        let parsed = Block::from_json(&json).unwrap();
+        assert_eq!(parsed, block);
+        assert_eq!(parsed.calculate_hash(), block.hash);
+    }
+
+    #[test]
+    fn test_pre_fork_hash_commits_to_transaction_json() {
+        let mut block = Block { index: 10, ..post_fork() };
+        assert_eq!(block.tx_data(), transactions_json(&block.transactions));
+        let before = block.calculate_hash();
+        block.transactions[1].amount += 1;
+        assert_ne!(block.calculate_hash(), before);
+    }
+
+    #[test]
+    fn test_hash_preimage_switches_at_fork() {
+        let genesis = Block::from_json(GENESIS_JSON).unwrap();
+        assert_eq!(genesis.hash_preimage(), b"01738368000[]058925356");
+
+        let last_legacy = Block { index: MERKLE_ROOT_FORK_HEIGHT - 1, ..post_fork() };
+        let first_merkle = Block { index: MERKLE_ROOT_FORK_HEIGHT, ..post_fork() };
+        let header = |b: &Block, tx_data: &str| {
+            format!("{}{}{}{}{}{}", b.index, b.timestamp, tx_data, b.previous_hash, b.nonce, b.difficulty).into_bytes()
+        };
+        assert_eq!(last_legacy.hash_preimage(), header(&last_legacy, &transactions_json(&last_legacy.transactions)));
+        assert_eq!(first_merkle.hash_preimage(), header(&first_merkle, &first_merkle.merkle_root));
+
+        for block in [genesis, last_legacy, first_merkle] {
+            assert_eq!(hash_to_hex(&sha256(&block.hash_preimage())), block.calculate_hash());
+        }
+    }
+
+    #[test]
+    fn test_parse_is_lenient_like_go() {
+        let json = r#"{"index":3,"transactions":null,"Extra":{"a":[1.5]},"hash":"h","Nonce":1,"Nonce":2}"#;
+        let block = Block::from_json(json).unwrap();
+        assert_eq!((block.index, block.hash.as_str(), block.nonce), (3, "h", 2));
+        assert!(block.transactions.is_empty() && block.merkle_root.is_empty());
+        assert_eq!(Block::from_json(r#"{"Difficulty":4294967296}"#), Err(ParseJsonError::Field("Difficulty")));
+        assert_eq!(Block::from_json(r#"{"DifficultyBits":-1}"#), Err(ParseJsonError::Field("DifficultyBits")));
+        assert_eq!(Block::from_json(r#"{"transactions":[{"amount":1.5}]}"#), Err(ParseJsonError::Field("amount")));
+        assert_eq!(Block::from_json(r#"{"Index":1,}"#), Err(ParseJsonError::Syntax(11)));
+        assert_eq!(Block::from_json(""), Err(ParseJsonError::Syntax(0)));
+    }
+}
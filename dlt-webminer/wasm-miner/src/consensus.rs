@@ -0,0 +1,298 @@
+//! Block validity under the node's consensus rules, so a miner can check a
+//! solution before `/block/submit` and an explorer can flag bad blocks.
+//! Ported from dilithiumcoin's block acceptance checks:
+//!
+//! - `Index` follows the previous block and `PreviousHash` links to it
+//! - `Hash` is the recomputed hash, and it meets `DifficultyBits`
+//! - `MerkleRoot` matches the transactions (optional before the fork)
+//! - exactly one coinbase, paying the block reward plus every fee
+//! - every other transaction pays at least the minimum fee
+//!
+//! Signatures and balances need chain state and aren't checked here.
+
+use core::fmt;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::block::Block;
+use crate::merkle::transaction_merkle_root;
+use crate::template::MERKLE_ROOT_FORK_HEIGHT;
+use crate::utils::hash_to_hex;
+
+/// Base units in one DLT.
+pub const DLT_UNIT: i64 = 100_000_000;
+
+/// Reward and fee rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConsensusParams {
+    /// Block reward before the first halving, in base units.
+    pub initial_reward: i64,
+    /// Blocks between halvings.
+    pub halving_interval: i64,
+    /// Lowest fee a non-coinbase transaction may pay, in base units.
+    pub min_fee: i64,
+}
+
+impl ConsensusParams {
+    pub const MAINNET: ConsensusParams =
+        ConsensusParams { initial_reward: 50 * DLT_UNIT, halving_interval: 250_000, min_fee: 10_000 };
+
+    /// Block reward at `height`, matching Go's GetBlockReward: halved
+    /// every `halving_interval` blocks, and nothing after 64 halvings.
+    pub fn block_reward(&self, height: i64) -> i64 {
+        let halvings = height / self.halving_interval;
+        if halvings >= 64 {
+            return 0;
+        }
+        self.initial_reward >> halvings
+    }
+}
+
+impl Default for ConsensusParams {
+    fn default() -> Self {
+        Self::MAINNET
+    }
+}
+
+/// Mainnet block reward at `height`.
+pub fn block_reward(height: i64) -> i64 {
+    ConsensusParams::MAINNET.block_reward(height)
+}
+
+/// What a block must build on: the previous block's index and hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrevHeader<'a> {
+    pub index: i64,
+    pub hash: &'a str,
+}
+
+/// One broken rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockError {
+    /// `Index` isn't one past the previous block's.
+    Index { expected: i64 },
+    /// `PreviousHash` isn't the previous block's hash.
+    PreviousHash { expected: String },
+    /// `Hash` isn't what the header hashes to.
+    Hash { expected: String },
+    /// The header's hash has fewer leading zero bits than required.
+    Difficulty { required_bits: u32 },
+    /// `MerkleRoot` isn't the root of the transactions.
+    MerkleRoot { expected: String },
+    /// Coinbase transactions found; there must be exactly one.
+    CoinbaseCount { count: usize },
+    /// The coinbase doesn't pay the reward plus fees.
+    CoinbaseAmount { expected: i64, actual: i64 },
+    /// The transaction at `position` pays less than the minimum fee.
+    Fee { position: usize, fee: i64 },
+}
+
+impl BlockError {
+    /// A stable name for the rule, for JS and logs.
+    pub fn code(&self) -> &'static str {
+        match self {
+            BlockError::Index { .. } => "index",
+            BlockError::PreviousHash { .. } => "previous-hash",
+            BlockError::Hash { .. } => "hash",
+            BlockError::Difficulty { .. } => "difficulty",
+            BlockError::MerkleRoot { .. } => "merkle-root",
+            BlockError::CoinbaseCount { .. } => "coinbase-count",
+            BlockError::CoinbaseAmount { .. } => "coinbase-amount",
+            BlockError::Fee { .. } => "fee",
+        }
+    }
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockError::Index { expected } => write!(f, "index should be {}", expected),
+            BlockError::PreviousHash { expected } => write!(f, "previous hash should be {}", expected),
+            BlockError::Hash { expected } => write!(f, "hash should be {}", expected),
+            BlockError::Difficulty { required_bits } => {
+                write!(f, "hash doesn't have {} leading zero bits", required_bits)
+            }
+            BlockError::MerkleRoot { expected } => write!(f, "Merkle root should be {}", expected),
+            BlockError::CoinbaseCount { count } => write!(f, "{} coinbase transactions, expected 1", count),
+            BlockError::CoinbaseAmount { expected, actual } => {
+                write!(f, "coinbase pays {}, expected reward plus fees of {}", actual, expected)
+            }
+            BlockError::Fee { position, fee } => write!(f, "transaction {} pays fee {}, below the minimum", position, fee),
+        }
+    }
+}
+
+/// Check `block` against every rule above, on top of `prev`. Returns all
+/// the rules it breaks, in the order listed, rather than just the first.
+pub fn validate_block(block: &Block, prev: &PrevHeader<'_>, params: &ConsensusParams) -> Result<(), Vec<BlockError>> {
+    let mut errors = Vec::new();
+
+    if prev.index.checked_add(1) != Some(block.index) {
+        errors.push(BlockError::Index { expected: prev.index.wrapping_add(1) });
+    }
+    if block.previous_hash != prev.hash {
+        errors.push(BlockError::PreviousHash { expected: String::from(prev.hash) });
+    }
+
+    let template = block.template();
+    let hash = template.finalize(block.nonce);
+    // Compared as strings, as the node does: uppercase hex is a mismatch
+    let hash_hex = hash_to_hex(&hash);
+    if block.hash != hash_hex {
+        errors.push(BlockError::Hash { expected: hash_hex });
+    }
+    if !template.target().is_met_by_bytes(&hash) {
+        errors.push(BlockError::Difficulty { required_bits: template.diff_bits() });
+    }
+
+    let merkle_root = transaction_merkle_root(&block.transactions);
+    if (block.index >= MERKLE_ROOT_FORK_HEIGHT || !block.merkle_root.is_empty()) && block.merkle_root != merkle_root {
+        errors.push(BlockError::MerkleRoot { expected: merkle_root });
+    }
+
+    let mut coinbases = block.transactions.iter().filter(|tx| tx.is_coinbase());
+    match (coinbases.next(), coinbases.count()) {
+        (Some(coinbase), 0) => {
+            let fees = block.transactions.iter().filter(|tx| !tx.is_coinbase()).fold(0i64, |sum, tx| sum.saturating_add(tx.fee));
+            let expected = params.block_reward(block.index).saturating_add(fees);
+            if coinbase.amount != expected {
+                errors.push(BlockError::CoinbaseAmount { expected, actual: coinbase.amount });
+            }
+        }
+        (first, rest) => errors.push(BlockError::CoinbaseCount { count: first.map_or(0, |_| 1 + rest) }),
+    }
+    for (position, tx) in block.transactions.iter().enumerate() {
+        if !tx.is_coinbase() && tx.fee < params.min_fee {
+            errors.push(BlockError::Fee { position, fee: tx.fee });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::Transaction;
+
+    const PREV_HASH: &str = "00000014a2f0c6b5e2d3f1a8c7b6e5d4c3b2a1908f7e6d5c4b3a29180f7e6d5c";
+
+    /// A valid block on top of `PREV_HASH`, mined at 8 bits.
+    fn valid(index: i64) -> Block {
+        let transfer = Transaction {
+            from: "alice".into(),
+            to: "bob".into(),
+            amount: 3 * DLT_UNIT,
+            fee: 25_000,
+            timestamp: 1738399000,
+            signature: "sig".into(),
+            ..Transaction::default()
+        };
+        let coinbase = Transaction {
+            from: "SYSTEM".into(),
+            to: "miner".into(),
+            amount: block_reward(index) + transfer.fee,
+            timestamp: 1738400000,
+            signature: format!("coinbase-{}-1", index),
+            ..Transaction::default()
+        };
+        let transactions = vec![coinbase, transfer];
+        let mut block = Block {
+            index,
+            timestamp: 1738400000,
+            merkle_root: transaction_merkle_root(&transactions),
+            transactions,
+            previous_hash: PREV_HASH.into(),
+            difficulty: 6,
+            difficulty_bits: 8,
+            ..Block::default()
+        };
+        let found = block.template().mining_job().unwrap().mine(0, 1, 10_000).expect("solution at 8 bits");
+        block.nonce = found.nonce;
+        block.hash = found.hash_hex;
+        block
+    }
+
+    fn check(block: &Block) -> Result<(), Vec<BlockError>> {
+        validate_block(block, &PrevHeader { index: block.index - 1, hash: PREV_HASH }, &ConsensusParams::MAINNET)
+    }
+
+    #[test]
+    fn test_block_reward() {
+        assert_eq!(block_reward(0), 5_000_000_000);
+        assert_eq!(block_reward(249_999), 5_000_000_000);
+        assert_eq!(block_reward(250_000), 2_500_000_000);
+        assert_eq!(block_reward(64 * 250_000), 0);
+    }
+
+    #[test]
+    fn test_valid_blocks_either_side_of_fork() {
+        assert_eq!(check(&valid(MERKLE_ROOT_FORK_HEIGHT + 1)), Ok(()));
+        assert_eq!(check(&valid(12)), Ok(()));
+        // Pre-fork blocks may leave the Merkle root out
+        let legacy = Block { merkle_root: String::new(), ..valid(12) };
+        assert_eq!(check(&legacy), Ok(()));
+    }
+
+    #[test]
+    fn test_header_errors() {
+        let block = valid(7000);
+        let prev = PrevHeader { index: 7000, hash: "00ff" };
+        assert_eq!(
+            validate_block(&block, &prev, &ConsensusParams::MAINNET),
+            Err(vec![BlockError::Index { expected: 7001 }, BlockError::PreviousHash { expected: "00ff".into() }])
+        );
+
+        let forged = Block { hash: "0".repeat(64), ..block.clone() };
+        assert_eq!(check(&forged), Err(vec![BlockError::Hash { expected: block.hash.clone() }]));
+        let shouting = Block { hash: block.hash.to_uppercase(), ..block.clone() };
+        assert_eq!(check(&shouting), Err(vec![BlockError::Hash { expected: block.hash.clone() }]));
+
+        // Claiming more work than the nonce did
+        let greedy = Block { difficulty_bits: 60, ..block };
+        let errors = check(&greedy).unwrap_err();
+        assert!(errors.contains(&BlockError::Difficulty { required_bits: 60 }), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_transaction_errors() {
+        // Changed transactions with the old root: the header still hashes
+        // fine, since post-fork it commits to the root, not the transactions
+        let mut block = valid(7000);
+        block.transactions[1].fee = 9_999;
+        block.transactions.push(block.transactions[0].clone());
+        let errors = check(&block).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                BlockError::MerkleRoot { expected: transaction_merkle_root(&block.transactions) },
+                BlockError::CoinbaseCount { count: 2 },
+                BlockError::Fee { position: 1, fee: 9_999 },
+            ]
+        );
+
+        // With a consistent root the rest of the header must be re-mined,
+        // so only check the transaction rules from here
+        let tx_rules = |b: &mut Block| {
+            b.merkle_root = transaction_merkle_root(&b.transactions);
+            let errors = check(b).unwrap_err();
+            errors.into_iter().filter(|e| !matches!(e, BlockError::Hash { .. } | BlockError::Difficulty { .. })).collect::<Vec<_>>()
+        };
+        block.transactions.truncate(2);
+        block.transactions[1].fee = 10_000;
+        assert_eq!(tx_rules(&mut block), vec![BlockError::CoinbaseAmount { expected: 5_000_010_000, actual: 5_000_025_000 }]);
+
+        block.transactions.remove(0);
+        assert_eq!(tx_rules(&mut block), vec![BlockError::CoinbaseCount { count: 0 }]);
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let error = BlockError::Fee { position: 2, fee: 5 };
+        assert_eq!((error.code(), error.to_string().as_str()), ("fee", "transaction 2 pays fee 5, below the minimum"));
+    }
+}
@@ -0,0 +1,341 @@
+//! PoW hashing backends for the mining loop.
+//!
+//! The loop never calls a SHA-256 kernel directly: it asks a `PowHasher`
+//! for the leading hash word of `LANES` nonces at a time, and for the full
+//! state of the rare candidates that pass the early reject. Every backend
+//! shares the `HashJob` precomputation and must agree with it bit for bit,
+//! so swapping one for another changes speed, never results.
+//!
+//! `Backend` names the built-in ones and picks the fastest this build can
+//! run; `MiningJob::set_backend` and `mining::mine_batch` take any of them,
+//! which is how they are benchmarked side by side.
+
+use crate::sha256::{sha256_of_state, split_midstate, HashJob, Sha256State};
+use crate::target::Target;
+
+/// Run `$body` with `$hasher` bound to the `PowHasher` for `$backend`, so
+/// generic code is monomorphized once per backend and dispatched once per
+/// call rather than per hash.
+#[cfg(any(feature = "alloc", test))]
+macro_rules! with_hasher {
+    ($backend:expr, $hasher:ident => $body:expr) => {
+        match $backend {
+            $crate::hasher::Backend::Scalar => {
+                let $hasher = &$crate::hasher::Scalar;
+                $body
+            }
+            #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+            $crate::hasher::Backend::Simd128 => {
+                let $hasher = &$crate::hasher::Simd128;
+                $body
+            }
+            // A backend the CPU lacks falls back to scalar rather than faulting
+            #[cfg(target_arch = "x86_64")]
+            $crate::hasher::Backend::Avx2 => match $crate::hasher::Avx2::detect() {
+                Some(hasher) => {
+                    let $hasher = &hasher;
+                    $body
+                }
+                None => {
+                    let $hasher = &$crate::hasher::Scalar;
+                    $body
+                }
+            },
+            #[cfg(target_arch = "x86_64")]
+            $crate::hasher::Backend::ShaNi => match $crate::hasher::ShaNi::detect() {
+                Some(hasher) => {
+                    let $hasher = &hasher;
+                    $body
+                }
+                None => {
+                    let $hasher = &$crate::hasher::Scalar;
+                    $body
+                }
+            },
+        }
+    };
+}
+#[cfg(feature = "alloc")]
+pub(crate) use with_hasher;
+
+/// `with_hasher!` for a `PowHash`: the backend as is for single SHA-256,
+/// wrapped in `DoubleSha256` for double.
+#[cfg(feature = "alloc")]
+macro_rules! with_pow_hasher {
+    ($backend:expr, $pow:expr, $hasher:ident => $body:expr) => {
+        $crate::hasher::with_hasher!($backend, inner => match $pow {
+            $crate::scheme::PowHash::Sha256 => {
+                let $hasher = inner;
+                $body
+            }
+            $crate::scheme::PowHash::DoubleSha256 => {
+                let $hasher = &$crate::hasher::DoubleSha256(inner);
+                $body
+            }
+        })
+    };
+}
+#[cfg(feature = "alloc")]
+pub(crate) use with_pow_hasher;
+
+/// Widest `PowHasher::LANES` the mining loop supports.
+pub const MAX_LANES: usize = 8;
+
+/// A SHA-256 backend for proof-of-work.
+pub trait PowHasher {
+    /// Short name for benchmarks and logs.
+    const NAME: &'static str;
+
+    /// Nonces hashed per `leading_words` call, 1 to `MAX_LANES`.
+    const LANES: usize;
+
+    /// Hash the whole 64-byte blocks of `prefix`, returning the state and
+    /// the unhashed tail.
+    fn midstate<'a>(&self, prefix: &'a [u8]) -> (Sha256State, &'a [u8]) {
+        split_midstate(prefix)
+    }
+
+    /// `HashJob::leading_word` for the first `LANES` nonces, which all
+    /// have `job.nonce_len()` digits. Later entries are ignored.
+    fn leading_words(&self, job: &HashJob, nonces: &[&[u8]; MAX_LANES]) -> [u32; MAX_LANES];
+
+    /// Full hash state for one nonce that passed the early reject.
+    fn hash(&self, job: &HashJob, nonce: &[u8]) -> [u32; 8] {
+        job.hash(nonce)
+    }
+
+    /// Does a full hash state meet `target`?
+    fn meets(&self, state: &[u32; 8], target: Target) -> bool {
+        target.is_met_by(state)
+    }
+}
+
+/// One nonce per compression pass. Runs everywhere.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Scalar;
+
+impl PowHasher for Scalar {
+    const NAME: &'static str = "scalar";
+    const LANES: usize = 1;
+
+    #[inline(always)]
+    fn leading_words(&self, job: &HashJob, nonces: &[&[u8]; MAX_LANES]) -> [u32; MAX_LANES] {
+        let mut words = [0; MAX_LANES];
+        words[0] = job.leading_word(nonces[0]);
+        words
+    }
+}
+
+/// Four nonces per pass across the lanes of a WASM SIMD128 vector.
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Simd128;
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+impl PowHasher for Simd128 {
+    const NAME: &'static str = "simd128";
+    const LANES: usize = 4;
+
+    #[inline(always)]
+    fn leading_words(&self, job: &HashJob, nonces: &[&[u8]; MAX_LANES]) -> [u32; MAX_LANES] {
+        let lead = crate::simd::leading_words_x4(job, [nonces[0], nonces[1], nonces[2], nonces[3]]);
+        let mut words = [0; MAX_LANES];
+        words[..4].copy_from_slice(&lead);
+        words
+    }
+}
+
+/// One nonce per pass on the x86 SHA extensions. Only constructed by
+/// `detect`, so holding one proves the CPU has them.
+#[cfg(target_arch = "x86_64")]
+#[derive(Clone, Copy, Debug)]
+pub struct ShaNi(());
+
+#[cfg(target_arch = "x86_64")]
+impl ShaNi {
+    pub fn detect() -> Option<ShaNi> {
+        #[cfg(feature = "std")]
+        let found = std::is_x86_feature_detected!("sha") && std::is_x86_feature_detected!("sse4.1");
+        #[cfg(not(feature = "std"))]
+        let found = cfg!(all(target_feature = "sha", target_feature = "sse4.1"));
+        found.then_some(ShaNi(()))
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl PowHasher for ShaNi {
+    const NAME: &'static str = "sha-ni";
+    const LANES: usize = 1;
+
+    fn midstate<'a>(&self, prefix: &'a [u8]) -> (Sha256State, &'a [u8]) {
+        let full = prefix.len() / 64 * 64;
+        let mut state = Sha256State::new();
+        // Safety: `self` exists only if detection found the SHA extensions
+        unsafe { crate::x86::compress_sha_ni(&mut state.h, &prefix[..full]) };
+        state.len = full as u64;
+        (state, &prefix[full..])
+    }
+
+    #[inline(always)]
+    fn leading_words(&self, job: &HashJob, nonces: &[&[u8]; MAX_LANES]) -> [u32; MAX_LANES] {
+        let mut words = [0; MAX_LANES];
+        words[0] = self.hash(job, nonces[0])[0];
+        words
+    }
+
+    #[inline(always)]
+    fn hash(&self, job: &HashJob, nonce: &[u8]) -> [u32; 8] {
+        // Safety: as above
+        unsafe { crate::x86::hash_job_sha_ni(job, nonce) }
+    }
+}
+
+/// Eight nonces per pass across the lanes of an AVX2 vector. Only
+/// constructed by `detect`.
+#[cfg(target_arch = "x86_64")]
+#[derive(Clone, Copy, Debug)]
+pub struct Avx2(());
+
+#[cfg(target_arch = "x86_64")]
+impl Avx2 {
+    pub fn detect() -> Option<Avx2> {
+        #[cfg(feature = "std")]
+        let found = std::is_x86_feature_detected!("avx2");
+        #[cfg(not(feature = "std"))]
+        let found = cfg!(target_feature = "avx2");
+        found.then_some(Avx2(()))
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl PowHasher for Avx2 {
+    const NAME: &'static str = "avx2";
+    const LANES: usize = 8;
+
+    #[inline(always)]
+    fn leading_words(&self, job: &HashJob, nonces: &[&[u8]; MAX_LANES]) -> [u32; MAX_LANES] {
+        // Safety: `self` exists only if detection found AVX2
+        unsafe { crate::x86::leading_words_x8(job, *nonces) }
+    }
+}
+
+/// Double SHA-256 on top of another backend: `H` hashes the header, a
+/// scalar pass hashes its digest. One nonce per pass, since the early
+/// reject needs the outer hash.
+#[derive(Clone, Copy, Debug)]
+pub struct DoubleSha256<'a, H>(pub &'a H);
+
+impl<H: PowHasher> PowHasher for DoubleSha256<'_, H> {
+    const NAME: &'static str = "sha256d";
+    const LANES: usize = 1;
+
+    fn midstate<'a>(&self, prefix: &'a [u8]) -> (Sha256State, &'a [u8]) {
+        self.0.midstate(prefix)
+    }
+
+    #[inline(always)]
+    fn leading_words(&self, job: &HashJob, nonces: &[&[u8]; MAX_LANES]) -> [u32; MAX_LANES] {
+        let mut words = [0; MAX_LANES];
+        words[0] = self.hash(job, nonces[0])[0];
+        words
+    }
+
+    #[inline(always)]
+    fn hash(&self, job: &HashJob, nonce: &[u8]) -> [u32; 8] {
+        sha256_of_state(&self.0.hash(job, nonce))
+    }
+}
+
+/// The built-in backends this build can run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Backend {
+    Scalar,
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    Simd128,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(target_arch = "x86_64")]
+    ShaNi,
+}
+
+impl Backend {
+    /// Every backend compiled into this build, slowest first. The x86 ones
+    /// also need CPU support; see `is_available`. AVX2 overtakes SHA-NI once
+    /// the job has several fixed blocks, but typical templates are short.
+    pub const ALL: &'static [Backend] = &[
+        Backend::Scalar,
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        Backend::Simd128,
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx2,
+        #[cfg(target_arch = "x86_64")]
+        Backend::ShaNi,
+    ];
+
+    /// Can this CPU run the backend? Unavailable ones fall back to scalar.
+    pub fn is_available(self) -> bool {
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx2 => Avx2::detect().is_some(),
+            #[cfg(target_arch = "x86_64")]
+            Backend::ShaNi => ShaNi::detect().is_some(),
+            _ => true,
+        }
+    }
+
+    /// The backends this CPU can run, slowest first.
+    pub fn available() -> impl Iterator<Item = Backend> {
+        Backend::ALL.iter().copied().filter(|b| b.is_available())
+    }
+
+    /// The fastest backend available.
+    pub fn detect() -> Backend {
+        Backend::available().last().unwrap_or(Backend::Scalar)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Scalar => Scalar::NAME,
+            #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+            Backend::Simd128 => Simd128::NAME,
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx2 => Avx2::NAME,
+            #[cfg(target_arch = "x86_64")]
+            Backend::ShaNi => ShaNi::NAME,
+        }
+    }
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Backend::detect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_lanes<H: PowHasher>(hasher: &H, job: &HashJob, nonces: &[&[u8]; MAX_LANES]) {
+        let words = hasher.leading_words(job, nonces);
+        for lane in 0..H::LANES {
+            assert_eq!(words[lane], job.leading_word(nonces[lane]), "{} lane {}", H::NAME, lane);
+            assert_eq!(hasher.hash(job, nonces[lane]), job.hash(nonces[lane]));
+        }
+    }
+
+    #[test]
+    fn test_backends_match_hash_job() {
+        let prefix = b"0173836800012ab34cd56ef7890ab12cd34ef567890abcdef0123456789abcdef00000abc";
+        let (state, tail) = Scalar.midstate(prefix);
+        let job = HashJob::new(state.h, state.len, tail, 4, b"6");
+        let digits: [[u8; 4]; MAX_LANES] = core::array::from_fn(|i| [b'1', b'0', b'0', b'0' + i as u8]);
+        let nonces: [&[u8]; MAX_LANES] = core::array::from_fn(|i| &digits[i][..]);
+
+        for backend in Backend::available() {
+            with_hasher!(backend, hasher => check_lanes(hasher, &job, &nonces));
+            with_hasher!(backend, hasher => assert_eq!(hasher.midstate(prefix), (state.clone(), tail)));
+        }
+        assert_eq!(Backend::default(), Backend::detect());
+    }
+}
@@ -0,0 +1,336 @@
+//! Just enough JSON reading for node blocks and transactions, without
+//! pulling serde into the WASM build.
+//!
+//! Follows Go's json.Unmarshal where the two could disagree: object keys
+//! match field names case-insensitively, missing fields keep their zero
+//! value, unknown ones are skipped, and the last of duplicate keys wins.
+//! Invalid `\u` surrogates decode to U+FFFD, as Go does.
+
+use core::fmt;
+
+use alloc::string::String;
+
+/// Why a block or transaction didn't parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseJsonError {
+    /// Malformed JSON at this byte offset.
+    Syntax(usize),
+    /// A known field with the wrong type, or an integer out of range.
+    Field(&'static str),
+}
+
+impl fmt::Display for ParseJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseJsonError::Syntax(offset) => write!(f, "malformed JSON at byte {}", offset),
+            ParseJsonError::Field(name) => write!(f, "field {} has the wrong type or is out of range", name),
+        }
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(src: &'a str) -> Self {
+        Reader { src: src.as_bytes(), pos: 0 }
+    }
+
+    fn syntax(&self) -> ParseJsonError {
+        ParseJsonError::Syntax(self.pos)
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.src.get(self.pos) {
+            self.pos += 1;
+        }
+        self.src.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), ParseJsonError> {
+        if self.peek() != Some(byte) {
+            return Err(self.syntax());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &[u8]) -> Result<(), ParseJsonError> {
+        if !self.src[self.pos..].starts_with(word) {
+            return Err(self.syntax());
+        }
+        self.pos += word.len();
+        Ok(())
+    }
+
+    /// Consume `null` if that's what comes next.
+    pub(crate) fn null(&mut self) -> Result<bool, ParseJsonError> {
+        if self.peek() == Some(b'n') {
+            self.literal(b"null")?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Nothing but whitespace left.
+    pub(crate) fn end(&mut self) -> Result<(), ParseJsonError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.syntax()),
+        }
+    }
+
+    /// Visit each key of an object. `field` must consume the value. A
+    /// `null` object is an empty one.
+    pub(crate) fn object(
+        &mut self,
+        mut field: impl FnMut(&mut Self, &str) -> Result<(), ParseJsonError>,
+    ) -> Result<(), ParseJsonError> {
+        if self.null()? {
+            return Ok(());
+        }
+        self.expect(b'{')?;
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            let key = self.string_raw()?;
+            self.expect(b':')?;
+            field(self, &key)?;
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.syntax()),
+            }
+        }
+    }
+
+    /// Visit each element of an array. `item` must consume it. A `null`
+    /// array is an empty one.
+    pub(crate) fn array(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<(), ParseJsonError>,
+    ) -> Result<(), ParseJsonError> {
+        if self.null()? {
+            return Ok(());
+        }
+        self.expect(b'[')?;
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            item(self)?;
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.syntax()),
+            }
+        }
+    }
+
+    /// A string field; `null` leaves it empty.
+    pub(crate) fn string(&mut self, name: &'static str) -> Result<String, ParseJsonError> {
+        match self.peek() {
+            Some(b'"') => self.string_raw(),
+            Some(b'n') => self.null().map(|_| String::new()),
+            Some(_) => Err(ParseJsonError::Field(name)),
+            None => Err(self.syntax()),
+        }
+    }
+
+    fn string_raw(&mut self) -> Result<String, ParseJsonError> {
+        self.expect(b'"')?;
+        let mut out = String::new();
+        loop {
+            // Copy the run up to the next quote, escape or control byte
+            let run = self.src[self.pos..].iter().position(|&b| b == b'"' || b == b'\\' || b < 0x20);
+            let Some(run) = run else {
+                self.pos = self.src.len();
+                return Err(self.syntax());
+            };
+            // The source is a &str and the run ends on an ASCII byte, so this
+            // slice is whole UTF-8
+            out.push_str(core::str::from_utf8(&self.src[self.pos..self.pos + run]).map_err(|_| self.syntax())?);
+            self.pos += run;
+            match self.src[self.pos] {
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                b'\\' => {
+                    self.pos += 1;
+                    let escape = *self.src.get(self.pos).ok_or_else(|| self.syntax())?;
+                    self.pos += 1;
+                    match escape {
+                        b'"' => out.push('"'),
+                        b'\\' => out.push('\\'),
+                        b'/' => out.push('/'),
+                        b'b' => out.push('\u{8}'),
+                        b'f' => out.push('\u{c}'),
+                        b'n' => out.push('\n'),
+                        b'r' => out.push('\r'),
+                        b't' => out.push('\t'),
+                        b'u' => out.push(self.unicode_escape()?),
+                        _ => {
+                            self.pos -= 1;
+                            return Err(self.syntax());
+                        }
+                    }
+                }
+                _ => return Err(self.syntax()),
+            }
+        }
+    }
+
+    /// The character after `\u`, pairing surrogates where they pair.
+    fn unicode_escape(&mut self) -> Result<char, ParseJsonError> {
+        let first = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&first) {
+            return Ok(char::from_u32(first).unwrap_or('\u{fffd}'));
+        }
+        if self.src[self.pos..].starts_with(b"\\u") {
+            let save = self.pos;
+            self.pos += 2;
+            let second = self.hex4()?;
+            if (0xDC00..0xE000).contains(&second) {
+                let c = 0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00);
+                return Ok(char::from_u32(c).unwrap_or('\u{fffd}'));
+            }
+            // Not a low surrogate: it stands on its own
+            self.pos = save;
+        }
+        Ok('\u{fffd}')
+    }
+
+    fn hex4(&mut self) -> Result<u32, ParseJsonError> {
+        let digits = self.src.get(self.pos..self.pos + 4).ok_or_else(|| self.syntax())?;
+        let mut value = 0;
+        for &d in digits {
+            let v = (d as char).to_digit(16).ok_or_else(|| self.syntax())?;
+            value = value << 4 | v;
+        }
+        self.pos += 4;
+        Ok(value)
+    }
+
+    /// An integer field; `null` leaves it 0. Fractions and exponents are
+    /// type errors, as they are for Go's int64.
+    pub(crate) fn int(&mut self, name: &'static str) -> Result<i64, ParseJsonError> {
+        match self.peek() {
+            Some(b'-' | b'0'..=b'9') => {}
+            Some(b'n') => return self.null().map(|_| 0),
+            Some(_) => return Err(ParseJsonError::Field(name)),
+            None => return Err(self.syntax()),
+        }
+        let start = self.pos;
+        self.number()?;
+        let text = core::str::from_utf8(&self.src[start..self.pos]).map_err(|_| self.syntax())?;
+        text.parse().map_err(|_| ParseJsonError::Field(name))
+    }
+
+    /// Step over a number, checking JSON's grammar.
+    fn number(&mut self) -> Result<(), ParseJsonError> {
+        let digits = |r: &mut Self| {
+            let n = r.src[r.pos..].iter().take_while(|b| b.is_ascii_digit()).count();
+            r.pos += n;
+            n
+        };
+        if self.src.get(self.pos) == Some(&b'-') {
+            self.pos += 1;
+        }
+        match self.src.get(self.pos) {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                digits(self);
+            }
+            _ => return Err(self.syntax()),
+        }
+        if self.src.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            if digits(self) == 0 {
+                return Err(self.syntax());
+            }
+        }
+        if let Some(b'e' | b'E') = self.src.get(self.pos) {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.src.get(self.pos) {
+                self.pos += 1;
+            }
+            if digits(self) == 0 {
+                return Err(self.syntax());
+            }
+        }
+        Ok(())
+    }
+
+    /// Step over any value, for unknown fields.
+    pub(crate) fn skip(&mut self) -> Result<(), ParseJsonError> {
+        match self.peek() {
+            Some(b'{') => self.object(|r, _| r.skip()),
+            Some(b'[') => self.array(|r| r.skip()),
+            Some(b'"') => self.string_raw().map(drop),
+            Some(b't') => self.literal(b"true"),
+            Some(b'f') => self.literal(b"false"),
+            Some(b'n') => self.literal(b"null"),
+            Some(_) => self.number(),
+            None => Err(self.syntax()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strings_and_escapes() {
+        let mut r = Reader::new(r#" "a\"\\\/\b\f\n\r\té<😀\ud800x" "#);
+        assert_eq!(r.string("s").unwrap(), "a\"\\/\u{8}\u{c}\n\r\té<😀\u{fffd}x");
+        assert_eq!(r.end(), Ok(()));
+        assert_eq!(Reader::new("\"raw\ncontrol\"").string("s"), Err(ParseJsonError::Syntax(4)));
+        assert_eq!(Reader::new(r#""\q""#).string("s"), Err(ParseJsonError::Syntax(2)));
+        assert_eq!(Reader::new("\"open").string("s"), Err(ParseJsonError::Syntax(5)));
+        assert_eq!(Reader::new("5").string("s"), Err(ParseJsonError::Field("s")));
+        assert_eq!(Reader::new("null").string("s"), Ok(String::new()));
+    }
+
+    #[test]
+    fn test_ints() {
+        assert_eq!(Reader::new("-9223372036854775808").int("n"), Ok(i64::MIN));
+        assert_eq!(Reader::new("9223372036854775808").int("n"), Err(ParseJsonError::Field("n")));
+        assert_eq!(Reader::new("1.5").int("n"), Err(ParseJsonError::Field("n")));
+        assert_eq!(Reader::new("1e3").int("n"), Err(ParseJsonError::Field("n")));
+        assert_eq!(Reader::new("\"1\"").int("n"), Err(ParseJsonError::Field("n")));
+        // A leading zero ends the number, leaving the rest as trailing junk
+        let mut r = Reader::new("01");
+        assert_eq!(r.int("n"), Ok(0));
+        assert_eq!(r.end(), Err(ParseJsonError::Syntax(1)));
+        assert_eq!(Reader::new("-").int("n"), Err(ParseJsonError::Syntax(1)));
+    }
+
+    #[test]
+    fn test_skip_nested() {
+        let mut r = Reader::new(r#"{"a":[1,-2.5e+3,{"b":null}],"c":true,"d":false,"e":"x"} "#);
+        let mut keys = Vec::new();
+        r.object(|r, key| {
+            keys.push(String::from(key));
+            r.skip()
+        })
+        .unwrap();
+        assert_eq!(r.end(), Ok(()));
+        assert_eq!(keys, ["a", "c", "d", "e"]);
+        assert!(Reader::new("[1,]").skip().is_err());
+        assert!(Reader::new(r#"{"a" 1}"#).skip().is_err());
+        assert!(Reader::new("tru").skip().is_err());
+    }
+}
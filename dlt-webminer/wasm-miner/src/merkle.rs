@@ -1,12 +1,15 @@
-/// Merkle root computation — Bitcoin-style binary Merkle tree.
-/// Ported from dilithiumcoin/blockchain.go:121-148
-///
-/// - Leaf = SHA-256(JSON(tx))
-/// - Odd leaves: duplicate last
-/// - Pair adjacent and SHA-256(left + right) up the tree
-/// - Empty list: SHA-256("")
+//! Merkle root computation — Bitcoin-style binary Merkle tree.
+//! Ported from dilithiumcoin/blockchain.go:121-148
+//!
+//! - Leaf = SHA-256(JSON(tx))
+//! - Odd leaves: duplicate last
+//! - Pair adjacent and SHA-256(left + right) up the tree
+//! - Empty list: SHA-256("")
+
+use alloc::{string::String, vec::Vec};
 
 use crate::sha256::sha256;
+use crate::transaction::Transaction;
 use crate::utils::hash_to_hex;
 
 /// Compute the Merkle root of transaction JSON strings.
@@ -15,21 +18,34 @@ use crate::utils::hash_to_hex;
 ///
 /// Returns the Merkle root as a lowercase hex string.
 pub fn compute_merkle_root(tx_json_strings: &[String]) -> String {
-    if tx_json_strings.is_empty() {
-        let h = sha256(b"");
-        return hash_to_hex(&h);
-    }
-
     // Hash each transaction JSON to get leaf nodes
-    let mut hashes: Vec<[u8; 32]> = tx_json_strings
+    root_of_leaves(tx_json_strings.iter().map(|json| sha256(json.as_bytes())).collect())
+}
+
+/// Merkle root of structured transactions, each serialized as Go would.
+pub fn transaction_merkle_root(txs: &[Transaction]) -> String {
+    let mut json = String::new();
+    let leaves = txs
         .iter()
-        .map(|json| sha256(json.as_bytes()))
+        .map(|tx| {
+            json.clear();
+            tx.write_json(&mut json);
+            sha256(json.as_bytes())
+        })
         .collect();
+    root_of_leaves(leaves)
+}
+
+fn root_of_leaves(mut hashes: Vec<[u8; 32]>) -> String {
+    if hashes.is_empty() {
+        let h = sha256(b"");
+        return hash_to_hex(&h);
+    }
 
     // Build tree upward
     while hashes.len() > 1 {
         // Duplicate last if odd
-        if hashes.len() % 2 != 0 {
+        if !hashes.len().is_multiple_of(2) {
             let last = *hashes.last().unwrap();
             hashes.push(last);
         }
@@ -85,4 +101,23 @@ mod tests {
 
         assert_eq!(root, expected);
     }
+
+    #[test]
+    fn test_transaction_merkle_root_matches_json() {
+        let txs: Vec<Transaction> = (0..5)
+            .map(|i| Transaction {
+                from: if i == 0 { "SYSTEM".into() } else { format!("from{}", i) },
+                to: "to<&>".into(),
+                amount: 100 * i,
+                fee: 10_000 * (i % 2),
+                timestamp: 1738368000 + i,
+                signature: format!("sig{}", i),
+                ..Transaction::default()
+            })
+            .collect();
+        for n in 0..=txs.len() {
+            let json: Vec<String> = txs[..n].iter().map(Transaction::to_json).collect();
+            assert_eq!(transaction_merkle_root(&txs[..n]), compute_merkle_root(&json), "{} txs", n);
+        }
+    }
 }
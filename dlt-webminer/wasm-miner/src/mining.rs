@@ -1,81 +1,901 @@
-/// Mining loop — batch nonce search with midstate optimization.
-/// Ported from dilithiumcoin/cmd/dilithium-cpu-gpu-miner/worker.go
+//! Mining loop — batch nonce search with midstate optimization.
+//! Ported from dilithiumcoin/cmd/dilithium-cpu-gpu-miner/worker.go
 
-use crate::sha256::mine_hash_check;
-use crate::utils::{write_i64, hash_to_hex};
+use core::fmt;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::hasher::{with_hasher, with_pow_hasher, Backend, DoubleSha256, PowHasher, Scalar, MAX_LANES};
+use crate::scheme::PowHash;
+use crate::sha256::{hash_to_bytes, HashJob, HASH_JOB_MAX_SUFFIX};
+use crate::target::Target;
+use crate::template::BlockTemplate;
+use crate::utils::{hash_to_hex, DecimalCounter};
 
 /// Result of a successful mine_batch call.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MiningResult {
     pub nonce: i64,
     pub hash_hex: String,
+    /// Timestamp the solution was mined with, for jobs built from a
+    /// `BlockTemplate`. May be later than the template's original one if
+    /// the job rolled it.
+    pub timestamp: Option<i64>,
+}
+
+/// Lowest hash a job has seen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BestHash {
+    pub nonce: i64,
+    pub hash_hex: String,
+    pub leading_zeros: u32,
+    pub timestamp: Option<i64>,
 }
 
-/// Mine a batch of nonces, returning the first that meets difficulty.
+/// What the hashes in a batch (or a whole session) looked like: the lowest
+/// one, and how many had each number of leading zero bits.
 ///
-/// Parameters:
-/// - h: midstate hash values (8 x u32)
-/// - prefix_tail: remaining prefix bytes after midstate boundary
-/// - suffix: difficulty string bytes
-/// - start_nonce: first nonce to try
-/// - stride: increment between nonces (for multi-worker distribution)
-/// - batch_size: how many nonces to try in this batch
-/// - diff_bits: required number of leading zero bits
-/// - midstate_len: number of bytes already processed into the midstate
+/// Every hash lands in the histogram, so it doubles as an exact hash count
+/// and as a record of real work done: about half of all hashes should sit
+/// in bucket 0, a quarter in bucket 1, and so on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashStats {
+    /// `histogram[n]` counts hashes with exactly `n` leading zero bits;
+    /// `histogram[32]` counts those with 32 or more.
+    pub histogram: [u64; 33],
+    best: Option<(i64, [u32; 8], Option<i64>)>,
+}
+
+impl Default for HashStats {
+    fn default() -> Self {
+        HashStats { histogram: [0; 33], best: None }
+    }
+}
+
+impl HashStats {
+    /// Total hashes counted.
+    pub fn hashes(&self) -> u64 {
+        self.histogram.iter().sum()
+    }
+
+    pub fn best(&self) -> Option<BestHash> {
+        self.best.map(|(nonce, state, timestamp)| BestHash {
+            nonce,
+            hash_hex: hash_to_hex(&hash_to_bytes(&state)),
+            leading_zeros: leading_zeros(&state),
+            timestamp,
+        })
+    }
+
+    /// Fold another set of stats into this one.
+    pub fn merge(&mut self, other: &HashStats) {
+        for (total, n) in self.histogram.iter_mut().zip(other.histogram.iter()) {
+            *total += n;
+        }
+        if let Some((nonce, state, timestamp)) = other.best {
+            self.offer(nonce, &state, timestamp);
+        }
+    }
+
+    /// Leading word of the best hash so far; anything above it can't be a
+    /// new best.
+    #[inline(always)]
+    fn best_leading_word(&self) -> u32 {
+        self.best.map_or(u32::MAX, |(_, state, _)| state[0])
+    }
+
+    #[inline(always)]
+    fn count(&mut self, h0: u32) {
+        self.histogram[h0.leading_zeros() as usize] += 1;
+    }
+
+    fn offer(&mut self, nonce: i64, state: &[u32; 8], timestamp: Option<i64>) {
+        // [u32; 8] compares word by word, i.e. as the big-endian hash
+        if self.best.is_none_or(|(_, best, _)| *state < best) {
+            self.best = Some((nonce, *state, timestamp));
+        }
+    }
+}
+
+/// Leading zero bits of a raw hash state.
+fn leading_zeros(state: &[u32; 8]) -> u32 {
+    let mut zeros = 0;
+    for &word in state {
+        zeros += word.leading_zeros();
+        if word != 0 {
+            break;
+        }
+    }
+    zeros
+}
+
+/// A nonce recorded by `mine_collect`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Share {
+    pub nonce: i64,
+    pub hash_hex: String,
+    pub timestamp: Option<i64>,
+    /// The hash also meets the block target.
+    pub is_block: bool,
+}
+
+/// Result of a `mine_collect` call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShareBatch {
+    /// Shares in nonce order.
+    pub shares: Vec<Share>,
+    /// Nonces scanned; less than the batch size if the share buffer filled.
+    pub scanned: u32,
+}
+
+/// Result of a `mine_for` call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimedBatch {
+    /// Nonces hashed.
+    pub hashes: u64,
+    /// Last nonce hashed, or None if none were.
+    pub last_nonce: Option<i64>,
+    pub result: Option<MiningResult>,
+}
+
+/// Result of a `mine_until` call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartialBatch {
+    /// Nonces hashed before a solution, the end of the range, or the stop.
+    pub scanned: u32,
+    pub result: Option<MiningResult>,
+    /// The stop check fired before the range was done.
+    pub cancelled: bool,
+}
+
+/// Nonces `mine_until` hashes between stop checks: a few milliseconds of
+/// work for one core, and a multiple of the 4-lane group size.
+pub const CANCEL_CHECK_INTERVAL: u32 = 4096;
+
+/// Batch size `mine_for` starts with before it has measured a hash rate:
+/// a few milliseconds even on a slow phone.
+const CALIBRATION_BATCH: u32 = 1024;
+
+/// Shortest batch `mine_for` aims for, in milliseconds, so the run-up to
+/// the deadline doesn't split into ever tinier batches.
+const MIN_SLICE_MS: f64 = 1.0;
+
+/// Why `check_nonce_range` refused a batch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonceRangeError {
+    NegativeStart,
+    NonPositiveStride,
+    /// The last nonce of the batch would be past `i64::MAX`.
+    Overflow,
+}
+
+impl fmt::Display for NonceRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NonceRangeError::NegativeStart => "start nonce is negative",
+            NonceRangeError::NonPositiveStride => "stride must be at least 1",
+            NonceRangeError::Overflow => "nonce range runs past i64::MAX",
+        })
+    }
+}
+
+/// Check that `count` nonces `start, start + stride, ...` are all
+/// non-negative i64s. The mining loops themselves wrap silently on i64
+/// overflow; callers taking nonces from outside should check first.
+pub fn check_nonce_range(start: i64, stride: i64, count: u32) -> Result<(), NonceRangeError> {
+    if start < 0 {
+        return Err(NonceRangeError::NegativeStart);
+    }
+    if stride < 1 {
+        return Err(NonceRangeError::NonPositiveStride);
+    }
+    let steps = (count as i64).saturating_sub(1);
+    stride
+        .checked_mul(steps)
+        .and_then(|span| start.checked_add(span))
+        .map(|_| ())
+        .ok_or(NonceRangeError::Overflow)
+}
+
+/// The bytes after the nonce are more than a mining job can hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SuffixTooLongError {
+    pub len: usize,
+}
+
+impl fmt::Display for SuffixTooLongError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes after the nonce, but a mining job takes at most {}", self.len, HASH_JOB_MAX_SUFFIX)
+    }
+}
+
+/// Check that `suffix` fits a mining job whatever the prefix and nonce,
+/// i.e. is at most `HASH_JOB_MAX_SUFFIX` bytes. Building a job panics on a
+/// longer one; callers taking suffixes from outside should check first.
+pub fn check_suffix(suffix: &[u8]) -> Result<(), SuffixTooLongError> {
+    if suffix.len() > HASH_JOB_MAX_SUFFIX {
+        return Err(SuffixTooLongError { len: suffix.len() });
+    }
+    Ok(())
+}
+
+/// When a template-backed job moves its timestamp forward. A zero field
+/// disables that limit; the default never rolls.
 ///
-/// Returns Some(MiningResult) if a valid nonce is found, None otherwise.
-#[inline(never)]
-pub fn mine_batch(
-    h: [u32; 8],
-    prefix_tail: &[u8],
-    suffix: &[u8],
-    start_nonce: i64,
-    stride: i64,
-    batch_size: u32,
-    diff_bits: u32,
+/// Limits are checked at the start of each `mine` call, so a roll happens
+/// on a batch boundary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RollPolicy {
+    /// Roll after this many nonces on one timestamp.
+    pub max_nonces: u64,
+    /// Roll once the timestamp is this many seconds behind the clock
+    /// passed to `mine_at`.
+    pub max_age_secs: i64,
+}
+
+/// A mining job: the midstate plus the block bytes around the nonce.
+///
+/// Built once per block template and reused across batches, so the tail
+/// and suffix aren't copied in again for every batch and the final-block
+/// precomputation survives between calls.
+///
+/// A job built from a `BlockTemplate` can also roll the template's
+/// timestamp (see `RollPolicy`): the prefix and midstate are rebuilt here
+/// and the nonce run starts over from the first nonce mined on the old
+/// timestamp, keeping nonces small and the timestamp fresh.
+///
+/// A clone carries the whole state, nonce position included; `pool` hands
+/// one to each thread.
+#[derive(Clone)]
+pub struct MiningJob {
+    mid_h: [u32; 8],
     midstate_len: u64,
-) -> Option<MiningResult> {
-    // Pre-allocate stack buffers for zero-allocation hot loop
-    let mut nonce_buf = [0u8; 20];
-    // remaining = prefix_tail + nonce_decimal + suffix
-    // Max size: prefix_tail (< 64) + nonce (up to 20 digits) + suffix (< 20) = ~104 bytes
-    let mut remaining = [0u8; 256];
+    prefix_tail: Vec<u8>,
+    suffix: Vec<u8>,
+    target: Target,
+    // Final-block precomputation for the most recent nonce length
+    hash_job: HashJob,
+    next_nonce: i64,
+    template: Option<BlockTemplate>,
+    roll: RollPolicy,
+    // Nonces tried on the current timestamp, and where that run started
+    nonces_on_timestamp: u64,
+    first_nonce: Option<i64>,
+    // Hash statistics for the last batch and since creation / reset_stats
+    batch_stats: HashStats,
+    session_stats: HashStats,
+    // Measured hashes per millisecond for sizing `mine_for` batches; 0
+    // until the first one
+    hash_rate: f64,
+    backend: Backend,
+    pow: PowHash,
+}
+
+impl MiningJob {
+    /// Build a job from the full prefix (everything before the nonce) and
+    /// suffix (everything after it), mining for `diff_bits` leading zero
+    /// bits. Use `set_target` for anything finer.
+    ///
+    /// Panics if `suffix` fails `check_suffix`.
+    pub fn new(prefix: &[u8], suffix: &[u8], diff_bits: u32) -> Self {
+        let (state, tail) = with_hasher!(Backend::detect(), hasher => hasher.midstate(prefix));
+        Self::from_midstate(state.h, state.len, tail, suffix, diff_bits)
+    }
+
+    /// Build a job from a `compute_midstate` result.
+    ///
+    /// Panics if `midstate_len` isn't a multiple of 64 or `suffix` fails
+    /// `check_suffix`.
+    pub fn from_midstate(
+        mid_h: [u32; 8],
+        midstate_len: u64,
+        prefix_tail: &[u8],
+        suffix: &[u8],
+        diff_bits: u32,
+    ) -> Self {
+        let hash_job = HashJob::new(mid_h, midstate_len, prefix_tail, 1, suffix);
+        MiningJob {
+            mid_h,
+            midstate_len,
+            prefix_tail: prefix_tail.to_vec(),
+            suffix: suffix.to_vec(),
+            target: Target::from_bits(diff_bits),
+            hash_job,
+            next_nonce: 0,
+            template: None,
+            roll: RollPolicy::default(),
+            nonces_on_timestamp: 0,
+            first_nonce: None,
+            batch_stats: HashStats::default(),
+            session_stats: HashStats::default(),
+            hash_rate: 0.0,
+            backend: Backend::detect(),
+            pow: PowHash::Sha256,
+        }
+    }
+
+    /// Build a job that owns `template` and may roll its timestamp. Errs
+    /// if the template's scheme puts more after the nonce than a job can
+    /// hash, e.g. long transaction JSON.
+    pub fn from_template(template: BlockTemplate, roll: RollPolicy) -> Result<Self, SuffixTooLongError> {
+        let suffix = template.suffix();
+        check_suffix(&suffix)?;
+        let mut job = Self::new(&template.prefix(), &suffix, 0);
+        job.target = template.target();
+        job.pow = template.scheme.hash();
+        job.template = Some(template);
+        job.roll = roll;
+        Ok(job)
+    }
+
+    /// Point the job at a new prefix and suffix, reusing its buffers.
+    /// Drops any template, so the job no longer rolls. Panics if `suffix`
+    /// fails `check_suffix`.
+    pub fn reset(&mut self, prefix: &[u8], suffix: &[u8], diff_bits: u32) {
+        self.load(prefix, suffix);
+        self.target = Target::from_bits(diff_bits);
+        self.template = None;
+        self.next_nonce = 0;
+        self.nonces_on_timestamp = 0;
+        self.first_nonce = None;
+    }
+
+    /// Point the job at a new template, keeping its roll policy. Errs,
+    /// leaving the job as it was, where `from_template` would.
+    pub fn reset_template(&mut self, template: BlockTemplate) -> Result<(), SuffixTooLongError> {
+        let suffix = template.suffix();
+        check_suffix(&suffix)?;
+        self.reset(&template.prefix(), &suffix, 0);
+        self.target = template.target();
+        self.pow = template.scheme.hash();
+        self.template = Some(template);
+        Ok(())
+    }
+
+    pub fn set_roll_policy(&mut self, roll: RollPolicy) {
+        self.roll = roll;
+    }
+
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Hash with `backend` from the next batch on, instead of the fastest
+    /// one detected. Results don't change, only speed.
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.backend = backend;
+    }
+
+    pub fn pow_hash(&self) -> PowHash {
+        self.pow
+    }
+
+    /// Hash the header with `pow` from the next batch on. Jobs built from a
+    /// template take it from the template's scheme; others default to
+    /// single SHA-256. `reset` keeps it.
+    pub fn set_pow_hash(&mut self, pow: PowHash) {
+        self.pow = pow;
+    }
+
+    /// Mine against `target` from the next batch on. It survives timestamp
+    /// rolls; `reset` and `reset_template` replace it.
+    pub fn set_target(&mut self, target: Target) {
+        self.target = target;
+    }
+
+    /// The template being mined, with its current (possibly rolled)
+    /// timestamp.
+    pub fn template(&self) -> Option<&BlockTemplate> {
+        self.template.as_ref()
+    }
+
+    /// Stats for the hashes of the most recent `mine` / `mine_collect` call.
+    pub fn batch_stats(&self) -> &HashStats {
+        &self.batch_stats
+    }
+
+    /// Stats for every hash since the job was built or `reset_stats` was
+    /// called. Survives `reset` and timestamp rolls, so the best hash is
+    /// the best of the session.
+    pub fn session_stats(&self) -> &HashStats {
+        &self.session_stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.batch_stats = HashStats::default();
+        self.session_stats = HashStats::default();
+    }
+
+    /// The nonce after the last one tried by `mine`: where the next batch
+    /// should start to carry on without gaps or repeats.
+    pub fn next_nonce(&self) -> i64 {
+        self.next_nonce
+    }
+
+    /// Try `count` nonces `start, start + stride, ...`, returning the
+    /// first that meets difficulty.
+    pub fn mine(&mut self, start: i64, stride: i64, count: u32) -> Option<MiningResult> {
+        self.mine_at(start, stride, count, None)
+    }
+
+    /// `mine` with the current unix time in seconds, for age-based rolling.
+    ///
+    /// If the roll policy says the timestamp is due, it is moved to `now`
+    /// (or one second on, if the clock hasn't passed it) and `start` is
+    /// replaced by the first nonce mined on the old timestamp. Check
+    /// `next_nonce` rather than assuming the batch ran from `start`.
+    pub fn mine_at(&mut self, start: i64, stride: i64, count: u32, now: Option<i64>) -> Option<MiningResult> {
+        with_pow_hasher!(self.backend, self.pow, hasher => self.mine_at_with(hasher, start, stride, count, now))
+    }
+
+    /// `mine` on `hasher` rather than the job's backend: any `PowHasher`,
+    /// built in or not. It still follows the job's `PowHash`.
+    pub fn mine_with<H: PowHasher>(&mut self, hasher: &H, start: i64, stride: i64, count: u32) -> Option<MiningResult> {
+        match self.pow {
+            PowHash::Sha256 => self.mine_at_with(hasher, start, stride, count, None),
+            PowHash::DoubleSha256 => self.mine_at_with(&DoubleSha256(hasher), start, stride, count, None),
+        }
+    }
+
+    fn mine_at_with<H: PowHasher>(
+        &mut self,
+        hasher: &H,
+        start: i64,
+        stride: i64,
+        count: u32,
+        now: Option<i64>,
+    ) -> Option<MiningResult> {
+        let start = self.begin_batch(start, now);
+        let mut stats = HashStats::default();
+        let found = self.scan_with(hasher, start, stride, count, self.target, &mut stats);
+
+        let scanned = match found {
+            Some((nonce, _)) => nonces_between(start, nonce, stride) + 1,
+            None => count,
+        };
+        self.end_batch(start, stride, scanned, stats);
+        found.map(|(nonce, state)| self.result(nonce, &state))
+    }
+
+    /// Mine from `start` for about `duration_ms`, sizing batches from the
+    /// measured hash rate. `clock` returns milliseconds from any fixed
+    /// origin; `now` is passed on to `mine_at` for age-based rolling.
+    ///
+    /// Each batch aims at half the time left, so a misjudged rate can't
+    /// overshoot the deadline by much, and the rate carries over to the
+    /// next call. Stops at a solution, the deadline or `i64::MAX`;
+    /// `batch_stats` covers the whole call.
+    pub fn mine_for(
+        &mut self,
+        start: i64,
+        stride: i64,
+        duration_ms: f64,
+        now: Option<i64>,
+        mut clock: impl FnMut() -> f64,
+    ) -> TimedBatch {
+        let deadline = clock() + duration_ms;
+        let mut timed = TimedBatch { hashes: 0, last_nonce: None, result: None };
+        let mut stats = HashStats::default();
+        let mut nonce = start;
+        let mut left = duration_ms;
+
+        while left > 0.0 {
+            let count = self.batch_for((left / 2.0).max(MIN_SLICE_MS)).min(nonces_until_max(nonce, stride));
+            if count == 0 {
+                break;
+            }
+            let began = clock();
+            let result = self.mine_at(nonce, stride, count, now);
+            let ended = clock();
+
+            let hashes = self.batch_stats.hashes();
+            self.measure(hashes, ended - began);
+            stats.merge(&self.batch_stats);
+            timed.hashes += hashes;
+            timed.last_nonce = Some(self.next_nonce.wrapping_sub(stride));
+            if result.is_some() {
+                timed.result = result;
+                break;
+            }
+            nonce = self.next_nonce;
+            left = deadline - ended;
+        }
+
+        self.batch_stats = stats;
+        timed
+    }
+
+    /// `mine_at`, checking `stop` before every `CANCEL_CHECK_INTERVAL`
+    /// nonces and bailing out once it returns true, so new work doesn't
+    /// wait for a long batch to run out.
+    ///
+    /// `stop` is typically a generation counter shared with whoever hands
+    /// out work:
+    ///
+    /// ```
+    /// # use std::sync::atomic::{AtomicU32, Ordering};
+    /// # use dlt_webminer::mining::MiningJob;
+    /// let generation = AtomicU32::new(7);
+    /// let mut job = MiningJob::new(b"prefix", b"6", 255);
+    /// let mine_gen = generation.load(Ordering::Relaxed);
+    /// let batch = job.mine_until(0, 1, 100_000, None, || generation.load(Ordering::Relaxed) != mine_gen);
+    /// assert!(!batch.cancelled);
+    /// ```
+    ///
+    /// `next_nonce` and `batch_stats` cover what was actually hashed.
+    pub fn mine_until(
+        &mut self,
+        start: i64,
+        stride: i64,
+        count: u32,
+        now: Option<i64>,
+        mut stop: impl FnMut() -> bool,
+    ) -> PartialBatch {
+        let start = self.begin_batch(start, now);
+        let mut stats = HashStats::default();
+        let mut found = None;
+        let mut cancelled = false;
+        let mut scanned = 0;
+        while scanned < count {
+            if stop() {
+                cancelled = true;
+                break;
+            }
+            let from = start.wrapping_add(stride.wrapping_mul(scanned as i64));
+            let chunk = (count - scanned).min(CANCEL_CHECK_INTERVAL);
+            if let Some((nonce, state)) = self.scan(from, stride, chunk, self.target, &mut stats) {
+                scanned += nonces_between(from, nonce, stride) + 1;
+                found = Some(self.result(nonce, &state));
+                break;
+            }
+            scanned += chunk;
+        }
+
+        self.end_batch(start, stride, scanned, stats);
+        PartialBatch { scanned, result: found, cancelled }
+    }
+
+    /// Scan the whole range, recording every nonce whose hash meets
+    /// `share`, and flagging those that also meet `block`.
+    ///
+    /// The scan stops early once `max_shares` (at least 1) shares are
+    /// recorded, so no share is ever dropped; `next_nonce` and
+    /// `ShareBatch::scanned` say where it stopped. A `block` target above
+    /// `share` raises the recording threshold to `block`. The job's own
+    /// target isn't used. Timestamp rolling applies as for `mine`.
+    pub fn mine_collect(
+        &mut self,
+        start: i64,
+        stride: i64,
+        count: u32,
+        share: Target,
+        block: Target,
+        max_shares: usize,
+    ) -> ShareBatch {
+        let start = self.begin_batch(start, None);
+        let record = share.max(block);
+        let max_shares = max_shares.max(1);
+
+        let mut stats = HashStats::default();
+        let mut shares = Vec::new();
+        let mut scanned = 0;
+        while scanned < count && shares.len() < max_shares {
+            // Resume just past the previous share
+            let from = start.wrapping_add(stride.wrapping_mul(scanned as i64));
+            match self.scan(from, stride, count - scanned, record, &mut stats) {
+                Some((nonce, state)) => {
+                    scanned += nonces_between(from, nonce, stride) + 1;
+                    let result = self.result(nonce, &state);
+                    shares.push(Share {
+                        nonce: result.nonce,
+                        hash_hex: result.hash_hex,
+                        timestamp: result.timestamp,
+                        is_block: block.is_met_by(&state),
+                    });
+                }
+                None => scanned = count,
+            }
+        }
+
+        self.end_batch(start, stride, scanned, stats);
+        ShareBatch { shares, scanned }
+    }
+
+    /// Roll the timestamp if due and return the nonce this batch really
+    /// starts at.
+    fn begin_batch(&mut self, start: i64, now: Option<i64>) -> i64 {
+        let start = if self.roll_due(now) {
+            self.roll_timestamp(now);
+            self.first_nonce.unwrap_or(start)
+        } else {
+            start
+        };
+        self.first_nonce.get_or_insert(start);
+        start
+    }
+
+    fn end_batch(&mut self, start: i64, stride: i64, scanned: u32, mut stats: HashStats) {
+        self.next_nonce = start.wrapping_add(stride.wrapping_mul(scanned as i64));
+        self.nonces_on_timestamp = self.nonces_on_timestamp.saturating_add(scanned as u64);
+
+        if let Some(best) = &mut stats.best {
+            best.2 = self.template.as_ref().map(|t| t.timestamp);
+        }
+        self.session_stats.merge(&stats);
+        self.batch_stats = stats;
+    }
+
+    /// Nonces that should take about `ms` at the measured rate.
+    fn batch_for(&self, ms: f64) -> u32 {
+        if self.hash_rate > 0.0 {
+            (self.hash_rate * ms).clamp(1.0, u32::MAX as f64) as u32
+        } else {
+            CALIBRATION_BATCH
+        }
+    }
+
+    fn measure(&mut self, hashes: u64, elapsed_ms: f64) {
+        if elapsed_ms >= 1.0 {
+            self.hash_rate = hashes as f64 / elapsed_ms;
+        } else {
+            // Under the clock's resolution (Date.now() ticks in whole ms):
+            // all we know is the rate is at least this
+            self.hash_rate = self.hash_rate.max(hashes as f64);
+        }
+    }
+
+    fn result(&self, nonce: i64, state: &[u32; 8]) -> MiningResult {
+        MiningResult {
+            nonce,
+            hash_hex: hash_to_hex(&hash_to_bytes(state)),
+            timestamp: self.template.as_ref().map(|t| t.timestamp),
+        }
+    }
+
+    /// First nonce in the range whose hash meets `target`, with its
+    /// final state, on the job's backend and `PowHash`. Every hash up to
+    /// and including that one is counted in `stats`.
+    #[inline(never)]
+    fn scan(
+        &mut self,
+        start: i64,
+        stride: i64,
+        count: u32,
+        target: Target,
+        stats: &mut HashStats,
+    ) -> Option<(i64, [u32; 8])> {
+        with_pow_hasher!(self.backend, self.pow, hasher => self.scan_with(hasher, start, stride, count, target, stats))
+    }
+
+    fn roll_due(&self, now: Option<i64>) -> bool {
+        let Some(template) = &self.template else {
+            return false;
+        };
+        let nonces_spent = self.roll.max_nonces > 0 && self.nonces_on_timestamp >= self.roll.max_nonces;
+        let too_old = match now {
+            Some(now) => self.roll.max_age_secs > 0 && now.saturating_sub(template.timestamp) >= self.roll.max_age_secs,
+            None => false,
+        };
+        nonces_spent || too_old
+    }
+
+    /// Move the template timestamp forward and rebuild the midstate.
+    fn roll_timestamp(&mut self, now: Option<i64>) {
+        let Some(template) = &mut self.template else {
+            return;
+        };
+        let next = template.timestamp.saturating_add(1);
+        let old = core::mem::replace(&mut template.timestamp, now.map_or(next, |now| now.max(next)));
+
+        let (prefix, suffix) = (template.prefix(), template.suffix());
+        if check_suffix(&suffix).is_err() {
+            // A timestamp after the nonce grew a digit too many; keep
+            // mining the old one
+            template.timestamp = old;
+            return;
+        }
+        self.load(&prefix, &suffix);
+        self.nonces_on_timestamp = 0;
+    }
+
+    /// Recompute the midstate and final-block layout for new bytes.
+    fn load(&mut self, prefix: &[u8], suffix: &[u8]) {
+        let (state, tail) = with_hasher!(self.backend, hasher => hasher.midstate(prefix));
+        self.mid_h = state.h;
+        self.midstate_len = state.len;
+        self.prefix_tail.clear();
+        self.prefix_tail.extend_from_slice(tail);
+        self.suffix.clear();
+        self.suffix.extend_from_slice(suffix);
+        self.hash_job = HashJob::new(state.h, state.len, tail, 1, suffix);
+    }
+
+    /// Final-block precomputation for `nonce_len`-digit nonces. Rebuilt
+    /// only when the digit count changes (a handful of times per 2^63
+    /// nonces).
+    #[inline(always)]
+    fn hash_job(&mut self, nonce_len: usize) -> &HashJob {
+        if self.hash_job.nonce_len() != nonce_len {
+            self.hash_job =
+                HashJob::new(self.mid_h, self.midstate_len, &self.prefix_tail, nonce_len, &self.suffix);
+        }
+        &self.hash_job
+    }
+
+    /// The mining loop: hashes `H::LANES` consecutive strided nonces per
+    /// pass and returns the first that meets `target`.
+    ///
+    /// A group whose nonces have different decimal lengths (e.g. 9998, 9999,
+    /// 10000, 10001) can't share a block layout, so its nonces are hashed one
+    /// at a time with `H::hash`, as is the `count % LANES` remainder. Either
+    /// way the lowest solving nonce wins and nothing past it is counted.
+    #[inline(never)]
+    fn scan_with<H: PowHasher>(
+        &mut self,
+        hasher: &H,
+        start: i64,
+        stride: i64,
+        count: u32,
+        target: Target,
+        stats: &mut HashStats,
+    ) -> Option<(i64, [u32; 8])> {
+        let lanes_n = H::LANES;
+        debug_assert!((1..=MAX_LANES).contains(&lanes_n));
+
+        // Decimal nonces, stepped in place -- only changed digits are
+        // rewritten. One odometer per lane, each stepping over the others.
+        // Lanes past the end of the batch may wrap near i64::MAX; they're
+        // never hashed.
+        let mut lanes: [DecimalCounter; MAX_LANES] = core::array::from_fn(|lane| {
+            let lane = lane.min(lanes_n - 1) as i64;
+            DecimalCounter::new(start.wrapping_add(lane.wrapping_mul(stride)), stride.wrapping_mul(lanes_n as i64))
+        });
 
-    let tail_len = prefix_tail.len();
-    let suffix_len = suffix.len();
+        // A hash is worth finishing if its leading word could meet the
+        // target or beat the batch's best. Both are rare after the
+        // first few hashes, so one compare covers them.
+        let diff_limit = target.leading_word();
+        let mut limit = diff_limit.max(stats.best_leading_word());
 
-    // Copy prefix_tail once (it doesn't change per nonce)
-    remaining[..tail_len].copy_from_slice(prefix_tail);
+        for _ in 0..count / lanes_n as u32 {
+            let nonce_len = lanes[0].len();
 
-    let mut nonce = start_nonce;
+            if lanes_n > 1 && lanes[lanes_n - 1].len() != nonce_len {
+                if let Some(found) = self.hash_each(hasher, &lanes[..lanes_n], target, stats) {
+                    return Some(found);
+                }
+                limit = diff_limit.max(stats.best_leading_word());
+                lanes[..lanes_n].iter_mut().for_each(DecimalCounter::advance);
+                continue;
+            }
 
-    for _ in 0..batch_size {
-        // Write nonce as decimal string
-        let nonce_len = write_i64(&mut nonce_buf, nonce);
+            // Only the nonce-dependent rounds are recomputed, and only up to
+            // the leading word. Candidates are rehashed in full; the rest are
+            // just counted.
+            let nonces: [&[u8]; MAX_LANES] = core::array::from_fn(|lane| lanes[lane.min(lanes_n - 1)].digits());
+            let lead = hasher.leading_words(self.hash_job(nonce_len), &nonces);
 
-        // Build remaining: prefix_tail + nonce + suffix
-        let total_rem_len = tail_len + nonce_len + suffix_len;
-        remaining[tail_len..tail_len + nonce_len].copy_from_slice(&nonce_buf[..nonce_len]);
-        remaining[tail_len + nonce_len..total_rem_len].copy_from_slice(suffix);
+            for (lane, &h0) in lanes[..lanes_n].iter().zip(lead.iter()) {
+                stats.count(h0);
+                if h0 <= limit {
+                    let state = hasher.hash(self.hash_job(nonce_len), lane.digits());
+                    stats.offer(lane.value(), &state, None);
+                    if hasher.meets(&state, target) {
+                        return Some((lane.value(), state));
+                    }
+                    limit = diff_limit.max(stats.best_leading_word());
+                }
+            }
 
-        // Compute hash from midstate + remaining, check difficulty on u32 words.
-        // Serialization to [u8;32] only happens if difficulty check passes (~0.01% of hashes).
-        if let Some(hash) = mine_hash_check(h, &remaining[..total_rem_len], midstate_len, diff_bits) {
-            return Some(MiningResult {
-                nonce,
-                hash_hex: hash_to_hex(&hash),
-            });
+            lanes[..lanes_n].iter_mut().for_each(DecimalCounter::advance);
         }
 
-        nonce += stride;
+        let rest = count % lanes_n as u32;
+        self.hash_each(hasher, &lanes[..rest as usize], target, stats)
     }
 
-    None
+    /// Hash `lanes`' nonces one at a time, in full, for the ones
+    /// `scan_with` can't batch.
+    fn hash_each<H: PowHasher>(
+        &mut self,
+        hasher: &H,
+        lanes: &[DecimalCounter],
+        target: Target,
+        stats: &mut HashStats,
+    ) -> Option<(i64, [u32; 8])> {
+        for lane in lanes {
+            let state = hasher.hash(self.hash_job(lane.len()), lane.digits());
+            stats.count(state[0]);
+            stats.offer(lane.value(), &state, None);
+            if hasher.meets(&state, target) {
+                return Some((lane.value(), state));
+            }
+        }
+        None
+    }
+}
+
+/// Nonces left from `nonce` up to `i64::MAX`, capped at a u32 batch.
+pub(crate) fn nonces_until_max(nonce: i64, stride: i64) -> u32 {
+    if nonce < 0 || stride < 1 {
+        return 0;
+    }
+    ((i64::MAX - nonce) / stride).saturating_add(1).min(u32::MAX as i64) as u32
+}
+
+/// Number of strides from `from` to `to`.
+#[inline]
+fn nonces_between(from: i64, to: i64, stride: i64) -> u32 {
+    (to.wrapping_sub(from) / stride) as u32
+}
+
+/// Try `count` nonces `start, start + stride, ...` with `hasher`, returning
+/// the first whose hash has `diff_bits` leading zero bits. One-shot form of
+/// `MiningJob::mine_with`, for callers that keep their own midstate:
+/// `h` is the state after the first `midstate_len` bytes of the header,
+/// `prefix_tail` the rest of the bytes before the nonce, and `suffix` the
+/// bytes after it (the decimal difficulty on mainnet).
+///
+/// `hasher` is any `PowHasher` (`&Scalar`, `ShaNi::detect()`, or your
+/// own); `mine_batch_scalar` fixes it to `Scalar`.
+#[allow(clippy::too_many_arguments)]
+pub fn mine_batch<H: PowHasher>(
+    hasher: &H,
+    h: [u32; 8],
+    prefix_tail: &[u8],
+    suffix: &[u8],
+    start: i64,
+    stride: i64,
+    count: u32,
+    diff_bits: u32,
+    midstate_len: u64,
+) -> Option<MiningResult> {
+    MiningJob::from_midstate(h, midstate_len, prefix_tail, suffix, diff_bits).mine_with(hasher, start, stride, count)
+}
+
+/// `mine_batch` on the scalar backend, regardless of target.
+#[allow(clippy::too_many_arguments)]
+pub fn mine_batch_scalar(
+    h: [u32; 8],
+    prefix_tail: &[u8],
+    suffix: &[u8],
+    start: i64,
+    stride: i64,
+    count: u32,
+    diff_bits: u32,
+    midstate_len: u64,
+) -> Option<MiningResult> {
+    mine_batch(&Scalar, h, prefix_tail, suffix, start, stride, count, diff_bits, midstate_len)
+}
+
+/// Collect every share in a batch. One-shot form of
+/// `MiningJob::mine_collect`.
+#[allow(clippy::too_many_arguments)]
+pub fn mine_batch_collect(
+    h: [u32; 8],
+    prefix_tail: &[u8],
+    suffix: &[u8],
+    start_nonce: i64,
+    stride: i64,
+    batch_size: u32,
+    share: Target,
+    block: Target,
+    midstate_len: u64,
+    max_shares: usize,
+) -> ShareBatch {
+    MiningJob::from_midstate(h, midstate_len, prefix_tail, suffix, 0)
+        .mine_collect(start_nonce, stride, batch_size, share, block, max_shares)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::sha256::compute_midstate;
+    use crate::utils::meets_difficulty_bytes;
 
     #[test]
     fn test_mine_batch_basic() {
@@ -87,6 +907,7 @@ mod tests {
 
         // This should not find a solution at difficulty 256 in 100 attempts
         let result = mine_batch(
+            &Scalar,
             state.h,
             &tail,
             suffix,
@@ -107,6 +928,7 @@ mod tests {
 
         // Difficulty 0 = any hash matches
         let result = mine_batch(
+            &Scalar,
             state.h,
             &tail,
             suffix,
@@ -119,4 +941,508 @@ mod tests {
         assert!(result.is_some());
         assert_eq!(result.unwrap().nonce, 0);
     }
+
+    #[test]
+    fn test_mine_batch_matches_scalar() {
+        // Low difficulty so solutions show up inside small batches. Start
+        // points straddle digit-count changes (999 -> 1000 etc.) and odd
+        // batch sizes exercise the non-multiple-of-4 remainder.
+        let prefix = b"6001173836800012ab34cd56ef7890ab12cd34ef567890abcdef0123456789abcdef00000abc";
+        let suffix = b"6";
+        let (state, tail) = compute_midstate(prefix);
+
+        for &(start, stride, batch, bits) in &[
+            (0i64, 1i64, 1000u32, 8u32),
+            (990, 1, 37, 6),
+            (9_990, 3, 403, 10),
+            (99_999_999, 7, 1001, 9),
+            (5, 4, 2, 0),
+        ] {
+            let scalar = mine_batch_scalar(state.h, &tail, suffix, start, stride, batch, bits, state.len);
+            let mut scalar_job = MiningJob::from_midstate(state.h, state.len, &tail, suffix, bits);
+            scalar_job.set_backend(Backend::Scalar);
+            scalar_job.mine(start, stride, batch);
+
+            for backend in Backend::available() {
+                let mut job = MiningJob::from_midstate(state.h, state.len, &tail, suffix, bits);
+                job.set_backend(backend);
+                assert_eq!(job.mine(start, stride, batch), scalar, "{} start={} stride={}", backend.name(), start, stride);
+                assert_eq!(job.next_nonce(), scalar_job.next_nonce());
+                assert_eq!(job.batch_stats(), scalar_job.batch_stats());
+            }
+        }
+    }
+
+    /// Three lanes of `Scalar`: a backend the loop has never seen, with a
+    /// lane count that doesn't divide anything evenly.
+    struct ThreeLanes;
+
+    impl PowHasher for ThreeLanes {
+        const NAME: &'static str = "three-lanes";
+        const LANES: usize = 3;
+
+        fn leading_words(&self, job: &HashJob, nonces: &[&[u8]; MAX_LANES]) -> [u32; MAX_LANES] {
+            core::array::from_fn(|lane| if lane < 3 { job.leading_word(nonces[lane]) } else { 0 })
+        }
+    }
+
+    #[test]
+    fn test_custom_hasher_plugs_into_loop() {
+        let prefix = b"6001173836800012ab34cd56ef7890ab12cd34ef567890abcdef0123456789abcdef00000abc";
+        let (state, tail) = compute_midstate(prefix);
+        for &(start, stride, batch, bits) in &[(0i64, 1i64, 1000u32, 9u32), (9_990, 3, 403, 10), (98, 1, 5, 0)] {
+            assert_eq!(
+                mine_batch(&ThreeLanes, state.h, &tail, b"6", start, stride, batch, bits, state.len),
+                mine_batch_scalar(state.h, &tail, b"6", start, stride, batch, bits, state.len),
+                "start={} stride={}",
+                start,
+                stride
+            );
+        }
+    }
+
+    /// Three lanes of double SHA-256, so a nonce hashed with anything but
+    /// its own `hash` comes out wrong.
+    struct ThreeLanesDouble;
+
+    impl PowHasher for ThreeLanesDouble {
+        const NAME: &'static str = "three-lanes-sha256d";
+        const LANES: usize = 3;
+
+        fn leading_words(&self, job: &HashJob, nonces: &[&[u8]; MAX_LANES]) -> [u32; MAX_LANES] {
+            core::array::from_fn(|lane| if lane < 3 { self.hash(job, nonces[lane])[0] } else { 0 })
+        }
+
+        fn hash(&self, job: &HashJob, nonce: &[u8]) -> [u32; 8] {
+            crate::sha256::sha256_of_state(&job.hash(nonce))
+        }
+    }
+
+    #[test]
+    fn test_unbatched_nonces_use_custom_hash() {
+        // 98, 99, 100 can't share a layout; a batch of 2 is all remainder
+        let prefix = b"6001173836800012ab34cd56ef7890ab12cd34ef567890abcdef0123456789abcdef00000abc";
+        let (state, tail) = compute_midstate(prefix);
+        for &(start, batch, bits) in &[(98i64, 5u32, 0u32), (101, 2, 0), (0, 1000, 9)] {
+            assert_eq!(
+                mine_batch(&ThreeLanesDouble, state.h, &tail, b"6", start, 1, batch, bits, state.len),
+                mine_batch(&DoubleSha256(&Scalar), state.h, &tail, b"6", start, 1, batch, bits, state.len),
+                "start={}",
+                start
+            );
+        }
+    }
+
+    #[test]
+    fn test_testnet_scheme_on_every_backend() {
+        let scheme = "sha256d:previous_hash,index,timestamp,nonce,tx_data,difficulty:bits".parse().unwrap();
+        let template = BlockTemplate::new(6001, 1738368000, &"a5".repeat(32), "0a0b", 6, 9).with_scheme(scheme);
+        let mut scalar_job = template.mining_job().unwrap();
+        assert_eq!(scalar_job.pow_hash(), PowHash::DoubleSha256);
+        scalar_job.set_backend(Backend::Scalar);
+        let expected = scalar_job.mine(0, 1, 5000).expect("solution at 9 bits");
+        assert!(template.verify(expected.nonce, &crate::utils::hex_to_hash(&expected.hash_hex).unwrap()));
+        // Single SHA-256 of the same bytes would be a different hash
+        assert_ne!(expected.hash_hex, hash_to_hex(&crate::sha256::sha256(&template.preimage(expected.nonce))));
+
+        for backend in Backend::available() {
+            let mut job = template.mining_job().unwrap();
+            job.set_backend(backend);
+            assert_eq!(job.mine(0, 1, 5000).as_ref(), Some(&expected), "{}", backend.name());
+            assert_eq!(job.batch_stats(), scalar_job.batch_stats());
+        }
+    }
+
+    #[test]
+    fn test_long_field_after_nonce() {
+        // A 63-byte prefix tail leaves the least room for the suffix
+        let scheme = "sha256:index,timestamp,previous_hash,nonce,tx_data,difficulty:legacy".parse().unwrap();
+        let tx_data = "t".repeat(HASH_JOB_MAX_SUFFIX - 1);
+        let template = BlockTemplate::new(7000, 1738368000, &tx_data, &"a".repeat(49), 6, 8).with_scheme(scheme);
+        assert_eq!((template.prefix().len() % 64, template.suffix().len()), (63, HASH_JOB_MAX_SUFFIX));
+        for start in [0, i64::MAX - 5000] {
+            let found = template.mining_job().unwrap().mine(start, 1, 5000).expect("solution at 8 bits");
+            assert!(template.verify(found.nonce, &crate::utils::hex_to_hash(&found.hash_hex).unwrap()));
+        }
+
+        // Pre-fork transaction JSON easily runs longer: it still hashes,
+        // but can't be mined
+        let long = BlockTemplate { tx_data: "t".repeat(300), ..template };
+        assert_eq!(long.mining_job().err(), Some(SuffixTooLongError { len: 301 }));
+        assert_eq!(long.finalize(0), crate::sha256::sha256(&long.preimage(0)));
+
+        // Nor may rolling a timestamp after the nonce grow the suffix past it
+        let scheme = "sha256:index,previous_hash,nonce,tx_data,timestamp,difficulty:legacy".parse().unwrap();
+        let tx_data = "t".repeat(HASH_JOB_MAX_SUFFIX - 11);
+        let edge = BlockTemplate::new(7000, 9_999_999_999, &tx_data, "00", 6, 255).with_scheme(scheme);
+        let mut job = MiningJob::from_template(edge.clone(), RollPolicy { max_nonces: 1, max_age_secs: 0 }).unwrap();
+        job.mine(0, 1, 10);
+        job.mine(job.next_nonce(), 1, 10);
+        assert_eq!(job.template(), Some(&edge));
+    }
+
+    #[test]
+    fn test_mining_job_batches_continue_from_next_nonce() {
+        let prefix = b"6001173836800012ab34cd56ef7890ab12cd34ef567890abcdef0123456789abcdef00000abc";
+        let suffix = b"6";
+        let (state, tail) = compute_midstate(prefix);
+        let one_shot = mine_batch(&Scalar, state.h, &tail, suffix, 0, 3, 3000, 11, state.len);
+
+        // Same search split into uneven batches through one job
+        let mut job = MiningJob::new(prefix, suffix, 11);
+        let mut found = None;
+        let mut nonce = 0;
+        for count in [7u32, 100, 1, 992, 1900] {
+            found = job.mine(nonce, 3, count);
+            if found.is_some() {
+                break;
+            }
+            nonce = job.next_nonce();
+        }
+        assert_eq!(found.map(|r| r.nonce), one_shot.as_ref().map(|r| r.nonce));
+
+        // After a hit, the job resumes just past the solution
+        if let Some(r) = one_shot {
+            assert_eq!(job.next_nonce(), r.nonce + 3);
+        }
+    }
+
+    #[test]
+    fn test_mining_job_reset_matches_fresh_job() {
+        let mut job = MiningJob::new(b"first template prefix", b"5", 4);
+        job.mine(0, 1, 50);
+
+        let prefix = b"second template prefix, long enough to cross a block boundary.....";
+        job.reset(prefix, b"6", 8);
+        assert_eq!(job.next_nonce(), 0);
+        assert_eq!(job.target(), Target::from_bits(8));
+
+        let a = job.mine(0, 1, 2000).map(|r| (r.nonce, r.hash_hex));
+        let b = MiningJob::new(prefix, b"6", 8).mine(0, 1, 2000).map(|r| (r.nonce, r.hash_hex));
+        assert!(a.is_some());
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_mining_job_rolls_after_max_nonces() {
+        let template = BlockTemplate::new(7000, 1_700_000_000, "ab".repeat(32).as_str(), "00", 6, 255);
+        let roll = RollPolicy { max_nonces: 100, max_age_secs: 0 };
+        let mut job = MiningJob::from_template(template.clone(), roll).unwrap();
+
+        assert!(job.mine(5, 2, 60).is_none());
+        assert_eq!(job.template().unwrap().timestamp, 1_700_000_000);
+        assert!(job.mine(job.next_nonce(), 2, 60).is_none());
+        assert_eq!(job.next_nonce(), 5 + 2 * 120);
+
+        // 120 nonces spent: the next batch rolls and restarts at nonce 5
+        assert!(job.mine(job.next_nonce(), 2, 10).is_none());
+        assert_eq!(job.template().unwrap().timestamp, 1_700_000_001);
+        assert_eq!(job.next_nonce(), 5 + 2 * 10);
+    }
+
+    #[test]
+    fn test_mining_job_rolls_on_age_and_reports_timestamp() {
+        let template = BlockTemplate::new(7000, 1_700_000_000, "cd".repeat(32).as_str(), "00", 6, 8);
+        let roll = RollPolicy { max_nonces: 0, max_age_secs: 30 };
+        let mut job = MiningJob::from_template(template.clone(), roll).unwrap();
+
+        // Not old enough yet
+        let found = job.mine_at(0, 1, 5000, Some(1_700_000_029)).expect("8-bit solution");
+        assert_eq!(found.timestamp, Some(1_700_000_000));
+
+        // Too old: jumps to the clock, and the solution verifies against the
+        // rolled template
+        let found = job.mine_at(found.nonce + 1, 1, 5000, Some(1_700_000_045)).expect("8-bit solution");
+        assert_eq!(found.timestamp, Some(1_700_000_045));
+        let mut rolled = template;
+        rolled.timestamp = 1_700_000_045;
+        assert_eq!(found.hash_hex, rolled.finalize_hex(found.nonce));
+        assert_eq!(job.template(), Some(&rolled));
+
+        // A clock from JS can be anything; one far in the past is never too old
+        job.mine_at(0, 1, 10, Some(i64::MIN));
+        assert_eq!(job.template(), Some(&rolled));
+    }
+
+    #[test]
+    fn test_mining_job_without_template_never_rolls() {
+        let mut job = MiningJob::new(b"raw prefix", b"6", 255);
+        job.set_roll_policy(RollPolicy { max_nonces: 1, max_age_secs: 1 });
+        job.mine_at(0, 1, 10, Some(i64::MAX));
+        assert_eq!(job.next_nonce(), 10);
+        assert!(job.template().is_none());
+    }
+
+    /// First solving nonce by the plain definition: full preimage through
+    /// `write_i64` and one-shot SHA-256.
+    fn reference_solution(template: &BlockTemplate, start: i64, stride: i64, count: u32) -> Option<i64> {
+        (0..count as i64)
+            .map(|i| start + i * stride)
+            .find(|&nonce| meets_difficulty_bytes(&template.finalize(nonce), template.diff_bits()))
+    }
+
+    #[test]
+    fn test_mining_near_i64_max() {
+        let template = BlockTemplate::new(6001, 1738368000, "ef".repeat(32).as_str(), "00ab", 6, 5);
+
+        // Across the 18 -> 19 digit boundary, and up to the last i64
+        for &(start, stride, count) in &[
+            (999_999_999_999_999_900i64, 1i64, 400u32),
+            (999_999_999_999_999_990, 7, 61),
+            (i64::MAX - 299, 1, 300),
+            (i64::MAX - 3 * 120, 3, 121),
+        ] {
+            check_nonce_range(start, stride, count).unwrap();
+            let mut job = template.mining_job().unwrap();
+            let found = job.mine(start, stride, count);
+            assert_eq!(found.as_ref().map(|r| r.nonce), reference_solution(&template, start, stride, count));
+            if let Some(r) = found {
+                assert_eq!(r.hash_hex, template.finalize_hex(r.nonce));
+            }
+        }
+
+        // Nothing solves at 255 bits, so the whole range runs to i64::MAX
+        let mut job = MiningJob::new(&template.prefix(), &template.suffix(), 255);
+        assert!(job.mine(i64::MAX - 10, 1, 11).is_none());
+    }
+
+    #[test]
+    fn test_check_nonce_range() {
+        assert_eq!(check_nonce_range(0, 1, 0), Ok(()));
+        assert_eq!(check_nonce_range(i64::MAX, 1, 1), Ok(()));
+        assert_eq!(check_nonce_range(i64::MAX - 10, 1, 11), Ok(()));
+        assert_eq!(check_nonce_range(i64::MAX - 10, 1, 12), Err(NonceRangeError::Overflow));
+        assert_eq!(check_nonce_range(1, i64::MAX, 3), Err(NonceRangeError::Overflow));
+        assert_eq!(check_nonce_range(-1, 1, 1), Err(NonceRangeError::NegativeStart));
+        assert_eq!(check_nonce_range(0, 0, 1), Err(NonceRangeError::NonPositiveStride));
+    }
+
+    #[test]
+    fn test_mine_collect_finds_every_share() {
+        let template = BlockTemplate::new(6001, 1738368000, "12".repeat(32).as_str(), "0a0b", 6, 9);
+        let (start, stride, count) = (997i64, 3i64, 3001u32);
+
+        let expected: Vec<(i64, bool)> = (0..count as i64)
+            .map(|i| start + i * stride)
+            .filter_map(|nonce| {
+                let hash = template.finalize(nonce);
+                meets_difficulty_bytes(&hash, 5).then(|| (nonce, meets_difficulty_bytes(&hash, 9)))
+            })
+            .collect();
+        assert!(expected.len() > 10 && expected.iter().any(|&(_, block)| block));
+
+        let mut job = template.mining_job().unwrap();
+        let batch = job.mine_collect(start, stride, count, Target::from_bits(5), Target::from_bits(9), 1000);
+        assert_eq!(batch.scanned, count);
+        assert_eq!(job.next_nonce(), start + stride * count as i64);
+        let got: Vec<(i64, bool)> = batch.shares.iter().map(|s| (s.nonce, s.is_block)).collect();
+        assert_eq!(got, expected);
+        for share in &batch.shares {
+            assert_eq!(share.hash_hex, template.finalize_hex(share.nonce));
+            assert_eq!(share.timestamp, Some(1738368000));
+        }
+
+        // Same shares from the one-shot form
+        let (state, tail) = compute_midstate(&template.prefix());
+        let one_shot = mine_batch_collect(
+            state.h, &tail, b"6", start, stride, count, Target::from_bits(5), Target::from_bits(9), state.len, 1000,
+        );
+        let nonces: Vec<i64> = one_shot.shares.iter().map(|s| s.nonce).collect();
+        assert_eq!(nonces, expected.iter().map(|&(n, _)| n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_mine_collect_stops_when_buffer_full() {
+        let template = BlockTemplate::new(6001, 1738368000, "34".repeat(32).as_str(), "0a0b", 6, 12);
+        let mut all = template.mining_job().unwrap();
+        let everything = all.mine_collect(0, 1, 4000, Target::from_bits(4), Target::from_bits(12), usize::MAX);
+        assert!(everything.shares.len() > 9);
+
+        // Three shares at a time, resuming from next_nonce, loses nothing
+        let mut job = template.mining_job().unwrap();
+        let mut nonce = 0;
+        let mut remaining = 4000;
+        let mut collected = Vec::new();
+        while remaining > 0 {
+            let batch = job.mine_collect(nonce, 1, remaining, Target::from_bits(4), Target::from_bits(12), 3);
+            assert!(batch.shares.len() <= 3);
+            remaining -= batch.scanned;
+            nonce = job.next_nonce();
+            collected.extend(batch.shares.into_iter().map(|s| s.nonce));
+        }
+        assert_eq!(collected, everything.shares.iter().map(|s| s.nonce).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_fractional_target() {
+        let template = BlockTemplate::new(6001, 1738368000, "78".repeat(32).as_str(), "0a0b", 6, 0);
+        let (start, stride, count) = (11i64, 1i64, 6000u32);
+        // Between 5 and 6 bits, and between 9 and 10 bits
+        let share = Target::from_difficulty(48.0).unwrap();
+        let block = Target::from_difficulty(768.0).unwrap();
+        let meets = |nonce: i64, target: Target| target.is_met_by_bytes(&template.finalize(nonce));
+
+        let expected: Vec<(i64, bool)> = (0..count as i64)
+            .map(|i| start + i * stride)
+            .filter(|&nonce| meets(nonce, share))
+            .map(|nonce| (nonce, meets(nonce, block)))
+            .collect();
+        // A fractional target keeps hashes a whole-bit one would round away
+        let five_bits = (0..count as i64).filter(|&i| meets(start + i, Target::from_bits(5))).count();
+        let six_bits = (0..count as i64).filter(|&i| meets(start + i, Target::from_bits(6))).count();
+        assert!(six_bits < expected.len() && expected.len() < five_bits);
+
+        let mut job = template.mining_job().unwrap();
+        let batch = job.mine_collect(start, stride, count, share, block, usize::MAX);
+        assert_eq!(batch.shares.iter().map(|s| (s.nonce, s.is_block)).collect::<Vec<_>>(), expected);
+
+        let first_block = expected.iter().find(|&&(_, is_block)| is_block).map(|&(nonce, _)| nonce);
+        assert!(first_block.is_some());
+        let mut job = template.mining_job().unwrap();
+        job.set_target(block);
+        assert_eq!(job.mine(start, stride, count).map(|r| r.nonce), first_block);
+    }
+
+    /// A clock that ticks 1 ms every time it is read.
+    fn ticking_clock() -> impl FnMut() -> f64 {
+        let mut ms = 0.0;
+        move || {
+            ms += 1.0;
+            ms
+        }
+    }
+
+    #[test]
+    fn test_mine_for_calibrates_and_stays_contiguous() {
+        let template = BlockTemplate::new(6001, 1738368000, "9a".repeat(32).as_str(), "0a0b", 6, 255);
+        let mut job = template.mining_job().unwrap();
+
+        // Uncalibrated: a 1024-nonce batch reads as 1 ms, leaving 2 ms, so
+        // the second batch is sized for the 1 ms minimum slice
+        let first = job.mine_for(5, 3, 4.0, None, ticking_clock());
+        assert!(first.result.is_none());
+        assert_eq!(first.hashes, 2 * CALIBRATION_BATCH as u64);
+        assert_eq!(first.last_nonce, Some(5 + 3 * (first.hashes as i64 - 1)));
+        assert_eq!(job.next_nonce(), first.last_nonce.unwrap() + 3);
+        assert_eq!(job.batch_stats().hashes(), first.hashes);
+
+        // The measured rate carries over: 3072, 6144 and 6144 nonces
+        let second = job.mine_for(job.next_nonce(), 3, 6.0, None, ticking_clock());
+        assert_eq!(second.hashes, 15_360);
+        assert_eq!(second.last_nonce, Some(first.last_nonce.unwrap() + 3 * 15_360));
+
+        // Nothing past i64::MAX
+        let end = job.mine_for(i64::MAX - 9, 1, 20.0, None, ticking_clock());
+        assert_eq!((end.hashes, end.last_nonce), (10, Some(i64::MAX)));
+        let last = job.mine_for(i64::MAX, 2, 20.0, None, ticking_clock());
+        assert_eq!(last.hashes, 1);
+    }
+
+    #[test]
+    fn test_mine_for_stops_at_solution() {
+        let template = BlockTemplate::new(6001, 1738368000, "bc".repeat(32).as_str(), "0a0b", 6, 14);
+        let expected = reference_solution(&template, 0, 1, 200_000).expect("solution at 14 bits");
+        let mut job = template.mining_job().unwrap();
+        let mut nonce = 0;
+        let found = loop {
+            let timed = job.mine_for(nonce, 1, 5.0, None, ticking_clock());
+            if let Some(found) = timed.result {
+                assert_eq!(timed.last_nonce, Some(found.nonce));
+                break found;
+            }
+            nonce = job.next_nonce();
+        };
+        assert_eq!(found.nonce, expected);
+        assert_eq!(job.next_nonce(), expected + 1);
+    }
+
+    #[test]
+    fn test_mine_until_stops_between_chunks() {
+        let template = BlockTemplate::new(6001, 1738368000, "de".repeat(32).as_str(), "0a0b", 6, 255);
+        let mut job = template.mining_job().unwrap();
+
+        // Stale on the third check: two chunks done, then out
+        let mut checks = 0;
+        let batch = job.mine_until(10, 2, 1_000_000, None, || {
+            checks += 1;
+            checks == 3
+        });
+        assert!(batch.cancelled && batch.result.is_none());
+        assert_eq!(batch.scanned, 2 * CANCEL_CHECK_INTERVAL);
+        assert_eq!(job.next_nonce(), 10 + 2 * 2 * CANCEL_CHECK_INTERVAL as i64);
+        assert_eq!(job.batch_stats().hashes(), batch.scanned as u64);
+
+        // Already stale: nothing hashed
+        let batch = job.mine_until(10, 2, 1_000_000, None, || true);
+        assert!(batch.cancelled);
+        assert_eq!((batch.scanned, job.next_nonce()), (0, 10));
+
+        // Never stale: runs to the end of a range that isn't a whole
+        // number of chunks
+        let batch = job.mine_until(0, 1, CANCEL_CHECK_INTERVAL + 5, None, || false);
+        assert!(!batch.cancelled);
+        assert_eq!(batch.scanned, CANCEL_CHECK_INTERVAL + 5);
+    }
+
+    #[test]
+    fn test_mine_until_matches_mine() {
+        let template = BlockTemplate::new(6001, 1738368000, "f0".repeat(32).as_str(), "0a0b", 6, 15);
+        let expected = reference_solution(&template, 0, 1, 500_000).expect("solution at 15 bits");
+        let batch = template.mining_job().unwrap().mine_until(0, 1, 500_000, None, || false);
+        assert!(!batch.cancelled);
+        assert_eq!(batch.result.map(|r| r.nonce), Some(expected));
+        assert_eq!(batch.scanned as i64, expected + 1);
+    }
+
+    #[test]
+    fn test_stats_match_reference() {
+        let template = BlockTemplate::new(6001, 1738368000, "56".repeat(32).as_str(), "0a0b", 6, 255);
+        let (start, stride, count) = (3i64, 5i64, 2003u32);
+        let hashes: Vec<(i64, [u8; 32])> = (0..count as i64)
+            .map(|i| start + i * stride)
+            .map(|nonce| (nonce, template.finalize(nonce)))
+            .collect();
+
+        let mut histogram = [0u64; 33];
+        for (_, hash) in &hashes {
+            let h0 = u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]);
+            histogram[h0.leading_zeros() as usize] += 1;
+        }
+        let &(best_nonce, best_hash) = hashes.iter().min_by_key(|(_, hash)| *hash).unwrap();
+
+        let mut job = template.mining_job().unwrap();
+        assert!(job.mine(start, stride, count).is_none());
+        let stats = job.batch_stats();
+        assert_eq!(stats.histogram, histogram);
+        assert_eq!(stats.hashes(), count as u64);
+        let best = stats.best().unwrap();
+        assert_eq!(best.nonce, best_nonce);
+        assert_eq!(best.hash_hex, hash_to_hex(&best_hash));
+        assert_eq!(best.leading_zeros, best_hash.iter().take_while(|&&b| b == 0).count() as u32 * 8
+            + best_hash.iter().find(|&&b| b != 0).unwrap().leading_zeros());
+        assert_eq!(best.timestamp, Some(1738368000));
+    }
+
+    #[test]
+    fn test_stats_stop_at_solution_and_accumulate() {
+        let template = BlockTemplate::new(6001, 1738368000, "78".repeat(32).as_str(), "0a0b", 6, 10);
+        let mut job = template.mining_job().unwrap();
+
+        let found = job.mine(0, 1, 100_000).expect("10-bit solution");
+        assert_eq!(job.batch_stats().hashes(), found.nonce as u64 + 1);
+        assert_eq!(job.batch_stats().best().unwrap().nonce, found.nonce);
+
+        // A second batch that finds nothing better leaves the session best
+        let mut weak = template.clone();
+        weak.difficulty_bits = 255;
+        job.reset_template(weak).unwrap();
+        job.mine(found.nonce + 1, 1, 50);
+        assert_eq!(job.batch_stats().hashes(), 50);
+        assert_eq!(job.session_stats().hashes(), found.nonce as u64 + 51);
+        assert_eq!(job.session_stats().best().unwrap().nonce, found.nonce);
+
+        job.reset_stats();
+        assert_eq!(job.session_stats().hashes(), 0);
+        assert!(job.session_stats().best().is_none());
+    }
 }
@@ -0,0 +1,339 @@
+//! Miner pool — one job mined by several threads sharing memory.
+//!
+//! Replaces per-worker WASM instances with JS striding the nonces: the pool
+//! holds a single job descriptor, each thread mines its own clone of it at
+//! `base + index, base + index + threads, ...`, and solutions land in a
+//! results ring that `poll` drains. New work bumps a generation counter the
+//! threads check every `CANCEL_CHECK_INTERVAL` nonces, so stale work stops
+//! within milliseconds and the threads pick up the new job without being
+//! respawned.
+//!
+//! Threads are started through a caller-supplied spawner: `std::thread`
+//! natively, Web Workers sharing the module's memory in a `threads` build.
+//!
+//! The pool's own methods never block on a lock, since in the browser
+//! they run on the main thread, where a contended std `Mutex` would
+//! `memory.atomic.wait` and trap. They spin on `try_lock` instead, or skip
+//! and leave the work for the next call; pool threads only hold the locks
+//! for a generation check, a job clone or a push.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, TryLockError};
+
+use crate::mining::{nonces_until_max, MiningJob, MiningResult};
+
+/// Solutions held for `poll`. Past this the oldest are dropped.
+pub const RESULTS_CAPACITY: usize = 64;
+
+/// Nonces per `mine_until` call on a pool thread. Only bounds how often
+/// the hash counter is updated; cancellation is checked far more often.
+const THREAD_BATCH: u32 = 1 << 18;
+
+/// Work for the pool's threads to run, handed to the spawner.
+pub type PoolThread = Box<dyn FnOnce() + Send>;
+
+/// A solution found by a pool thread.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolSolution {
+    /// Generation of the job it solves, as returned by `start`.
+    pub generation: u32,
+    pub thread: usize,
+    pub result: MiningResult,
+}
+
+/// What `poll` reports.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolStatus {
+    /// Generation of the job being mined.
+    pub generation: u32,
+    /// Hashes done by every thread since the pool was created.
+    pub hashes: u64,
+    /// Threads still running.
+    pub running: usize,
+    /// Solutions since the last poll, oldest first.
+    pub solutions: Vec<PoolSolution>,
+}
+
+struct Work {
+    generation: u32,
+    job: Option<MiningJob>,
+    stopped: bool,
+}
+
+struct Shared {
+    work: Mutex<Work>,
+    changed: Condvar,
+    // Copy of `work.generation` the mining loops can read without the lock
+    generation: AtomicU32,
+    hashes: AtomicU64,
+    running: AtomicUsize,
+    results: Mutex<VecDeque<PoolSolution>>,
+}
+
+impl Shared {
+    fn work(&self) -> MutexGuard<'_, Work> {
+        // A thread that panicked mid-update leaves nothing half-written
+        self.work.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// `work()` for the pool's owner: spins rather than waits.
+    fn work_spin(&self) -> MutexGuard<'_, Work> {
+        loop {
+            match self.work.try_lock() {
+                Ok(work) => return work,
+                Err(TryLockError::Poisoned(poisoned)) => return poisoned.into_inner(),
+                Err(TryLockError::WouldBlock) => std::hint::spin_loop(),
+            }
+        }
+    }
+
+    /// Move to the next generation with `job` (None idles the threads).
+    fn publish(&self, work: &mut Work, job: Option<MiningJob>) -> u32 {
+        work.generation = work.generation.wrapping_add(1);
+        work.job = job;
+        self.generation.store(work.generation, Ordering::Release);
+        self.changed.notify_all();
+        work.generation
+    }
+}
+
+/// Drops the running count when a pool thread ends, or if it never ran.
+struct Running(Arc<Shared>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0.running.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// A fixed set of mining threads sharing one job.
+pub struct MinerPool {
+    shared: Arc<Shared>,
+    threads: usize,
+}
+
+impl Default for MinerPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MinerPool {
+    /// A pool with no threads yet; `start` spawns them.
+    pub fn new() -> Self {
+        MinerPool {
+            shared: Arc::new(Shared {
+                work: Mutex::new(Work { generation: 0, job: None, stopped: false }),
+                changed: Condvar::new(),
+                generation: AtomicU32::new(0),
+                hashes: AtomicU64::new(0),
+                running: AtomicUsize::new(0),
+                results: Mutex::new(VecDeque::new()),
+            }),
+            threads: 0,
+        }
+    }
+
+    /// Mine `job` on `threads` threads (at least 1), starting at its
+    /// `next_nonce`, and return the job's generation.
+    ///
+    /// Threads already running on the same count switch to the new job
+    /// once they next check; otherwise the old ones are stopped and
+    /// `spawn` is called once per new thread. The first solution retires
+    /// the job: every thread idles until the next `start`.
+    pub fn start(&mut self, job: &MiningJob, threads: usize, mut spawn: impl FnMut(PoolThread)) -> u32 {
+        let threads = threads.max(1);
+        if threads != self.threads {
+            self.stop();
+            let hashes = self.shared.hashes.load(Ordering::Relaxed);
+            *self = MinerPool::new();
+            self.shared.hashes.store(hashes, Ordering::Relaxed);
+            self.threads = threads;
+            for index in 0..threads {
+                self.shared.running.fetch_add(1, Ordering::AcqRel);
+                let running = Running(self.shared.clone());
+                spawn(Box::new(move || run(running, index, threads)));
+            }
+        }
+        let mut work = self.shared.work_spin();
+        self.shared.publish(&mut work, Some(job.clone()))
+    }
+
+    /// Hash count, live threads, and the solutions found since the last
+    /// call. A solution a thread is recording right now turns up on the
+    /// next call.
+    pub fn poll(&self) -> PoolStatus {
+        let solutions = match self.shared.results.try_lock() {
+            Ok(mut results) => results.drain(..).collect(),
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner().drain(..).collect(),
+            Err(TryLockError::WouldBlock) => Vec::new(),
+        };
+        PoolStatus {
+            generation: self.shared.generation.load(Ordering::Acquire),
+            hashes: self.shared.hashes.load(Ordering::Relaxed),
+            running: self.shared.running.load(Ordering::Acquire),
+            solutions,
+        }
+    }
+
+    /// Tell every thread to exit once it next checks. Doesn't wait; `poll`
+    /// reports when `running` reaches 0.
+    pub fn stop(&mut self) {
+        let mut work = self.shared.work_spin();
+        work.stopped = true;
+        self.shared.publish(&mut work, None);
+        self.threads = 0;
+    }
+}
+
+impl Drop for MinerPool {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Body of pool thread `index` of `threads`.
+fn run(running: Running, index: usize, threads: usize) {
+    let shared = &running.0;
+    let mut seen = 0;
+    loop {
+        let (generation, mut job) = {
+            let mut work = shared.work();
+            loop {
+                if work.stopped {
+                    return;
+                }
+                match &work.job {
+                    Some(job) if work.generation != seen => break (work.generation, job.clone()),
+                    _ => work = shared.changed.wait(work).unwrap_or_else(|poisoned| poisoned.into_inner()),
+                }
+            }
+        };
+        seen = generation;
+
+        let stride = threads as i64;
+        let mut nonce = job.next_nonce().wrapping_add(index as i64);
+        let stale = || shared.generation.load(Ordering::Acquire) != generation;
+        loop {
+            let count = THREAD_BATCH.min(nonces_until_max(nonce, stride));
+            if count == 0 {
+                break;
+            }
+            let batch = job.mine_until(nonce, stride, count, None, stale);
+            shared.hashes.fetch_add(batch.scanned as u64, Ordering::Relaxed);
+            if let Some(result) = batch.result {
+                found(shared, PoolSolution { generation, thread: index, result });
+                break;
+            }
+            if batch.cancelled {
+                break;
+            }
+            nonce = job.next_nonce();
+        }
+    }
+}
+
+/// Record a solution and retire its job, unless newer work beat it.
+fn found(shared: &Shared, solution: PoolSolution) {
+    let mut work = shared.work();
+    if work.generation != solution.generation {
+        return;
+    }
+    shared.publish(&mut work, None);
+    drop(work);
+
+    let mut results = shared.results.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if results.len() == RESULTS_CAPACITY {
+        results.pop_front();
+    }
+    results.push_back(solution);
+}
+
+// std::thread::spawn is unsupported on wasm32-wasip1
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+    use crate::template::BlockTemplate;
+    use std::time::{Duration, Instant};
+
+    fn spawn_std(thread: PoolThread) {
+        std::thread::spawn(thread);
+    }
+
+    /// Poll until `done` holds, or panic after 30 seconds.
+    fn poll_until(pool: &MinerPool, mut done: impl FnMut(&PoolStatus) -> bool) -> PoolStatus {
+        let deadline = Instant::now() + Duration::from_secs(30);
+        loop {
+            let status = pool.poll();
+            if done(&status) {
+                return status;
+            }
+            assert!(Instant::now() < deadline, "pool never got there");
+            std::thread::sleep(Duration::from_millis(2));
+        }
+    }
+
+    #[test]
+    fn test_pool_finds_and_retires_solution() {
+        let template = BlockTemplate::new(6001, 1738368000, "a5".repeat(32).as_str(), "0a0b", 6, 12);
+        let mut pool = MinerPool::new();
+        let generation = pool.start(&template.mining_job().unwrap(), 3, spawn_std);
+
+        let status = poll_until(&pool, |s| !s.solutions.is_empty());
+        let solution = &status.solutions[0];
+        assert_eq!(solution.generation, generation);
+        assert_eq!(solution.result.hash_hex, template.finalize_hex(solution.result.nonce));
+        assert_eq!(solution.result.nonce % 3, solution.thread as i64);
+        assert!(template.target().is_met_by_bytes(&template.finalize(solution.result.nonce)));
+
+        // The job retired: the generation moved on and nothing more turns up
+        assert_ne!(pool.poll().generation, generation);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(pool.poll().solutions.is_empty());
+
+        pool.stop();
+        poll_until(&pool, |s| s.running == 0);
+    }
+
+    #[test]
+    fn test_pool_switches_work_without_respawning() {
+        let stale = BlockTemplate::new(6001, 1738368000, "b6".repeat(32).as_str(), "0a0b", 6, 255);
+        let fresh = BlockTemplate::new(6002, 1738368060, "c7".repeat(32).as_str(), "0a0b", 6, 10);
+        let mut pool = MinerPool::new();
+        let spawned = std::cell::Cell::new(0);
+        let spawn = |thread: PoolThread| {
+            spawned.set(spawned.get() + 1);
+            spawn_std(thread);
+        };
+
+        pool.start(&stale.mining_job().unwrap(), 2, spawn);
+        poll_until(&pool, |s| s.hashes > 0);
+        let generation = pool.start(&fresh.mining_job().unwrap(), 2, spawn);
+        let status = poll_until(&pool, |s| !s.solutions.is_empty());
+        assert_eq!(spawned.get(), 2);
+        assert_eq!(status.solutions.len(), 1);
+        assert_eq!(status.solutions[0].generation, generation);
+        assert_eq!(status.solutions[0].result.hash_hex, fresh.finalize_hex(status.solutions[0].result.nonce));
+
+        // A different thread count replaces the threads
+        pool.start(&stale.mining_job().unwrap(), 1, spawn);
+        assert_eq!(spawned.get(), 3);
+        poll_until(&pool, |s| s.running == 1);
+        drop(pool);
+    }
+
+    #[test]
+    fn test_poll_skips_results_a_thread_holds() {
+        let pool = MinerPool::new();
+        let result = MiningResult { nonce: 7, hash_hex: "00".repeat(32), timestamp: None };
+        let solution = PoolSolution { generation: 1, thread: 0, result };
+
+        // A blocking lock would deadlock here, as it would wait in a browser
+        let mut held = pool.shared.results.lock().unwrap();
+        held.push_back(solution.clone());
+        assert!(pool.poll().solutions.is_empty());
+        drop(held);
+        assert_eq!(pool.poll().solutions, [solution]);
+    }
+}
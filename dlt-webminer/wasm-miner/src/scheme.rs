@@ -0,0 +1,385 @@
+//! Proof-of-work scheme — what a block hash is computed over, and how.
+//!
+//! Mainnet hashes the Go `CalculateHash` string once with SHA-256:
+//!
+//!   SHA-256(index + timestamp + txData + previousHash + nonce + difficulty)
+//!
+//! with the legacy hex-digit difficulty in the header. Private testnets can
+//! change all three parts: the hash function, the order of the header
+//! fields, and which difficulty value is hashed. `PowScheme::MAINNET` is the
+//! default wherever a scheme is taken.
+//!
+//! A scheme is written as `hash:fields:difficulty`, e.g. mainnet is
+//! `sha256:index,timestamp,tx_data,previous_hash,nonce,difficulty:legacy`.
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::sha256::{sha256, sha256_of_state, Sha256Hasher};
+use crate::target::Target;
+use crate::template::required_bits;
+use crate::utils::{write_i32, write_i64};
+
+/// The hash function over the serialized header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PowHash {
+    Sha256,
+    /// SHA-256 of the SHA-256 digest.
+    DoubleSha256,
+}
+
+impl PowHash {
+    pub fn name(self) -> &'static str {
+        match self {
+            PowHash::Sha256 => "sha256",
+            PowHash::DoubleSha256 => "sha256d",
+        }
+    }
+
+    /// Final hash state from the state after the first SHA-256 pass.
+    #[inline]
+    pub fn finish(self, first: [u32; 8]) -> [u32; 8] {
+        match self {
+            PowHash::Sha256 => first,
+            PowHash::DoubleSha256 => sha256_of_state(&first),
+        }
+    }
+}
+
+/// One field of the hashed header. Every field is written as it is in
+/// `CalculateHash`: integers in plain decimal, strings as-is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HeaderField {
+    Index,
+    Timestamp,
+    /// Merkle root hex, or the transaction JSON before the Merkle fork.
+    TxData,
+    PreviousHash,
+    Nonce,
+    Difficulty,
+}
+
+impl HeaderField {
+    pub fn name(self) -> &'static str {
+        match self {
+            HeaderField::Index => "index",
+            HeaderField::Timestamp => "timestamp",
+            HeaderField::TxData => "tx_data",
+            HeaderField::PreviousHash => "previous_hash",
+            HeaderField::Nonce => "nonce",
+            HeaderField::Difficulty => "difficulty",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "index" => HeaderField::Index,
+            "timestamp" => HeaderField::Timestamp,
+            "tx_data" => HeaderField::TxData,
+            "previous_hash" => HeaderField::PreviousHash,
+            "nonce" => HeaderField::Nonce,
+            "difficulty" => HeaderField::Difficulty,
+            _ => return None,
+        })
+    }
+}
+
+/// Which difficulty value the `Difficulty` field hashes. The target is the
+/// same either way: `required_bits` leading zero bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DifficultyEncoding {
+    /// The legacy hex-digit `Difficulty`, as mainnet does.
+    Legacy,
+    /// The required leading zero bits.
+    Bits,
+}
+
+impl DifficultyEncoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            DifficultyEncoding::Legacy => "legacy",
+            DifficultyEncoding::Bits => "bits",
+        }
+    }
+}
+
+/// Header fields minus the nonce, borrowed. What a scheme serializes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeaderFields<'a> {
+    pub index: i64,
+    pub timestamp: i64,
+    pub tx_data: &'a str,
+    pub previous_hash: &'a str,
+    /// Legacy difficulty: required leading zero hex digits.
+    pub difficulty: i32,
+    /// Required leading zero bits, or 0 to derive them from `difficulty`.
+    pub difficulty_bits: u32,
+}
+
+impl HeaderFields<'_> {
+    /// Leading zero bits a solution needs.
+    pub fn diff_bits(&self) -> u32 {
+        required_bits(self.difficulty, self.difficulty_bits)
+    }
+}
+
+/// Why a scheme string didn't parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParsePowSchemeError {
+    /// Not three `:`-separated parts.
+    Format,
+    UnknownHash,
+    UnknownField,
+    /// The fields aren't each header field exactly once.
+    FieldOrder,
+    UnknownDifficulty,
+}
+
+impl fmt::Display for ParsePowSchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ParsePowSchemeError::Format => "scheme must be hash:fields:difficulty",
+            ParsePowSchemeError::UnknownHash => "hash must be sha256 or sha256d",
+            ParsePowSchemeError::UnknownField => "unknown header field",
+            ParsePowSchemeError::FieldOrder => "fields must list each header field exactly once",
+            ParsePowSchemeError::UnknownDifficulty => "difficulty must be legacy or bits",
+        })
+    }
+}
+
+/// How a block's proof-of-work hash is computed.
+///
+/// The field order always holds each `HeaderField` once, so the bytes
+/// before the nonce can be midstated and the ones after it become the
+/// mining suffix. Any order can be hashed and verified, but mining needs
+/// that suffix to be at most `HASH_JOB_MAX_SUFFIX` bytes: a long field
+/// after the nonce, such as pre-fork transaction JSON, makes
+/// `BlockTemplate::mining_job` fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PowScheme {
+    hash: PowHash,
+    fields: [HeaderField; 6],
+    difficulty: DifficultyEncoding,
+}
+
+impl Default for PowScheme {
+    fn default() -> Self {
+        PowScheme::MAINNET
+    }
+}
+
+impl PowScheme {
+    /// Go's `CalculateHash`.
+    pub const MAINNET: PowScheme = PowScheme {
+        hash: PowHash::Sha256,
+        fields: [
+            HeaderField::Index,
+            HeaderField::Timestamp,
+            HeaderField::TxData,
+            HeaderField::PreviousHash,
+            HeaderField::Nonce,
+            HeaderField::Difficulty,
+        ],
+        difficulty: DifficultyEncoding::Legacy,
+    };
+
+    /// None unless `fields` holds each header field exactly once.
+    pub fn new(hash: PowHash, fields: [HeaderField; 6], difficulty: DifficultyEncoding) -> Option<Self> {
+        let all_present = PowScheme::MAINNET.fields.iter().all(|field| fields.contains(field));
+        all_present.then_some(PowScheme { hash, fields, difficulty })
+    }
+
+    pub fn hash(&self) -> PowHash {
+        self.hash
+    }
+
+    pub fn fields(&self) -> &[HeaderField; 6] {
+        &self.fields
+    }
+
+    pub fn difficulty(&self) -> DifficultyEncoding {
+        self.difficulty
+    }
+
+    fn nonce_index(&self) -> usize {
+        self.fields.iter().position(|&f| f == HeaderField::Nonce).unwrap_or(self.fields.len())
+    }
+
+    /// Fields hashed before the nonce: the mining prefix.
+    pub fn before_nonce(&self) -> &[HeaderField] {
+        &self.fields[..self.nonce_index()]
+    }
+
+    /// Fields hashed after the nonce: the mining suffix.
+    pub fn after_nonce(&self) -> &[HeaderField] {
+        &self.fields[self.nonce_index() + 1..]
+    }
+
+    /// Feed the bytes of `fields` to `out`, in order.
+    pub fn write_fields(&self, header: &HeaderFields, fields: &[HeaderField], nonce: i64, mut out: impl FnMut(&[u8])) {
+        let mut digits = [0u8; 20];
+        for field in fields {
+            let len = match field {
+                HeaderField::TxData => {
+                    out(header.tx_data.as_bytes());
+                    continue;
+                }
+                HeaderField::PreviousHash => {
+                    out(header.previous_hash.as_bytes());
+                    continue;
+                }
+                HeaderField::Index => write_i64(&mut digits, header.index),
+                HeaderField::Timestamp => write_i64(&mut digits, header.timestamp),
+                HeaderField::Nonce => write_i64(&mut digits, nonce),
+                HeaderField::Difficulty => match self.difficulty {
+                    DifficultyEncoding::Legacy => write_i32(&mut digits, header.difficulty),
+                    DifficultyEncoding::Bits => write_i64(&mut digits, header.diff_bits() as i64),
+                },
+            };
+            out(&digits[..len]);
+        }
+    }
+
+    /// Block hash for `nonce`, with the header hashed as it streams in.
+    pub fn header_hash(&self, header: &HeaderFields, nonce: i64) -> [u8; 32] {
+        let mut hasher = Sha256Hasher::new();
+        self.write_fields(header, &self.fields, nonce, |bytes| hasher.update(bytes));
+        let first = hasher.finalize();
+        match self.hash {
+            PowHash::Sha256 => first,
+            PowHash::DoubleSha256 => sha256(&first),
+        }
+    }
+
+    /// The target a hash of `header` must meet.
+    pub fn target(&self, header: &HeaderFields) -> Target {
+        Target::from_bits(header.diff_bits())
+    }
+
+    /// Is `hash` the hash of `header` at `nonce`, and does it meet the
+    /// header's target?
+    pub fn verify(&self, header: &HeaderFields, nonce: i64, hash: &[u8; 32]) -> bool {
+        self.header_hash(header, nonce) == *hash && self.target(header).is_met_by_bytes(hash)
+    }
+}
+
+impl fmt::Display for PowScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.hash.name())?;
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            f.write_str(field.name())?;
+        }
+        write!(f, ":{}", self.difficulty.name())
+    }
+}
+
+impl FromStr for PowScheme {
+    type Err = ParsePowSchemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let (Some(hash), Some(fields), Some(difficulty)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(ParsePowSchemeError::Format);
+        };
+        if parts.next().is_some() {
+            return Err(ParsePowSchemeError::Format);
+        }
+
+        let hash = match hash {
+            "sha256" => PowHash::Sha256,
+            "sha256d" => PowHash::DoubleSha256,
+            _ => return Err(ParsePowSchemeError::UnknownHash),
+        };
+        let mut order = PowScheme::MAINNET.fields;
+        let mut names = fields.split(',');
+        for slot in order.iter_mut() {
+            let name = names.next().ok_or(ParsePowSchemeError::FieldOrder)?;
+            *slot = HeaderField::from_name(name.trim()).ok_or(ParsePowSchemeError::UnknownField)?;
+        }
+        if names.next().is_some() {
+            return Err(ParsePowSchemeError::FieldOrder);
+        }
+        let difficulty = match difficulty {
+            "legacy" => DifficultyEncoding::Legacy,
+            "bits" => DifficultyEncoding::Bits,
+            _ => return Err(ParsePowSchemeError::UnknownDifficulty),
+        };
+        PowScheme::new(hash, order, difficulty).ok_or(ParsePowSchemeError::FieldOrder)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS: HeaderFields<'static> = HeaderFields {
+        index: 0,
+        timestamp: 1738368000,
+        tx_data: "[]",
+        previous_hash: "0",
+        difficulty: 6,
+        difficulty_bits: 0,
+    };
+
+    fn preimage(scheme: &PowScheme, header: &HeaderFields, nonce: i64) -> Vec<u8> {
+        let mut out = Vec::new();
+        scheme.write_fields(header, scheme.fields(), nonce, |b| out.extend_from_slice(b));
+        out
+    }
+
+    #[test]
+    fn test_mainnet_is_calculate_hash() {
+        let scheme = PowScheme::default();
+        assert_eq!(scheme, PowScheme::MAINNET);
+        assert_eq!(preimage(&scheme, &GENESIS, 5892535), b"01738368000[]058925356");
+        let hash = scheme.header_hash(&GENESIS, 5892535);
+        assert_eq!(
+            crate::utils::HashHex(&hash).to_string(),
+            "0000002835112676fbe3d7588fa08557751aa4045cc8575f16037247350815ae"
+        );
+        assert!(scheme.verify(&GENESIS, 5892535, &hash));
+        assert!(!scheme.verify(&GENESIS, 5892536, &hash));
+    }
+
+    #[test]
+    fn test_testnet_scheme() {
+        let scheme: PowScheme = "sha256d:previous_hash,index,nonce,timestamp,tx_data,difficulty:bits".parse().unwrap();
+        assert_eq!(scheme.hash(), PowHash::DoubleSha256);
+        assert_eq!(scheme.before_nonce(), &[HeaderField::PreviousHash, HeaderField::Index]);
+        assert_eq!(scheme.after_nonce(), &[HeaderField::Timestamp, HeaderField::TxData, HeaderField::Difficulty]);
+
+        // Difficulty 6 hashes as 24 bits
+        let bytes = preimage(&scheme, &GENESIS, 42);
+        assert_eq!(bytes, b"00421738368000[]24");
+        assert_eq!(scheme.header_hash(&GENESIS, 42), sha256(&sha256(&bytes)));
+        assert_eq!(scheme.target(&GENESIS), PowScheme::MAINNET.target(&GENESIS));
+        assert_eq!(PowHash::DoubleSha256.finish(crate::sha256::INIT_H), sha256_of_state(&crate::sha256::INIT_H));
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let mainnet = "sha256:index,timestamp,tx_data,previous_hash,nonce,difficulty:legacy";
+        assert_eq!(mainnet.parse(), Ok(PowScheme::MAINNET));
+        assert_eq!(PowScheme::MAINNET.to_string(), mainnet);
+        let testnet = "sha256d:nonce,difficulty,index,timestamp,tx_data,previous_hash:bits";
+        assert_eq!(testnet.parse::<PowScheme>().unwrap().to_string(), testnet);
+
+        let bad = [
+            ("sha256:index", ParsePowSchemeError::Format),
+            ("md5:index,timestamp,tx_data,previous_hash,nonce,difficulty:legacy", ParsePowSchemeError::UnknownHash),
+            ("sha256:index,timestamp,tx_data,previous_hash,nonce,height:legacy", ParsePowSchemeError::UnknownField),
+            ("sha256:index,index,tx_data,previous_hash,nonce,difficulty:legacy", ParsePowSchemeError::FieldOrder),
+            ("sha256:index,timestamp,tx_data,previous_hash,nonce:legacy", ParsePowSchemeError::FieldOrder),
+            ("sha256:index,timestamp,tx_data,previous_hash,nonce,difficulty,index:legacy", ParsePowSchemeError::FieldOrder),
+            ("sha256:index,timestamp,tx_data,previous_hash,nonce,difficulty:hex", ParsePowSchemeError::UnknownDifficulty),
+            ("sha256:index,timestamp,tx_data,previous_hash,nonce,difficulty:legacy:x", ParsePowSchemeError::Format),
+        ];
+        for (spec, err) in bad {
+            assert_eq!(spec.parse::<PowScheme>(), Err(err), "{}", spec);
+        }
+    }
+}
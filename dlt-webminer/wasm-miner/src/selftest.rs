@@ -0,0 +1,161 @@
+//! Known-answer self-test and hash rate benchmark, for running on the
+//! user's own device rather than only under `cargo test`.
+//!
+//! A miscompiled or misoptimized engine (a broken SIMD lowering, say) fails
+//! quietly: it mines, it just never finds a valid block. `self_test` checks
+//! the standard SHA-256 vectors, the genesis block hash, and that every
+//! backend this device can run finds the genesis nonce through the real
+//! mining loop. `benchmark` times that same loop.
+//!
+//! The mining checks and `benchmark` need `alloc`.
+
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use crate::hasher::{with_hasher, Backend, PowHasher};
+use crate::sha256::{mine_hash, sha256, split_midstate};
+#[cfg(feature = "alloc")]
+use crate::target::Target;
+#[cfg(feature = "alloc")]
+use crate::template::BlockTemplate;
+use crate::template::header_hash;
+use crate::utils::hex_to_hash;
+
+const GENESIS_NONCE: i64 = 5892535;
+const GENESIS_HASH: &str = "0000002835112676fbe3d7588fa08557751aa4045cc8575f16037247350815ae";
+const MIDSTATE_MESSAGE: &[u8] =
+    b"1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef_extra_nonce_data_here";
+
+/// The first vector that gave the wrong answer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SelfTestFailure {
+    pub vector: &'static str,
+    /// Backend that got it wrong, for the mining vectors.
+    pub backend: Option<&'static str>,
+}
+
+impl fmt::Display for SelfTestFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "self-test vector {} failed", self.vector)?;
+        if let Some(backend) = self.backend {
+            write!(f, " on the {} backend", backend)?;
+        }
+        Ok(())
+    }
+}
+
+fn check(vector: &'static str, ok: bool) -> Result<(), SelfTestFailure> {
+    if ok {
+        Ok(())
+    } else {
+        Err(SelfTestFailure { vector, backend: None })
+    }
+}
+
+/// Run every known-answer vector. Takes a few milliseconds.
+pub fn self_test() -> Result<(), SelfTestFailure> {
+    check(
+        "sha256-empty",
+        hex_to_hash("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855") == Some(sha256(b"")),
+    )?;
+    check(
+        "sha256-abc",
+        hex_to_hash("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad") == Some(sha256(b"abc")),
+    )?;
+
+    let (state, tail) = split_midstate(MIDSTATE_MESSAGE);
+    check("midstate", mine_hash(state.h, tail, state.len) == sha256(MIDSTATE_MESSAGE))?;
+
+    let genesis = header_hash(0, 1738368000, "[]", "0", GENESIS_NONCE, 6);
+    check("genesis", hex_to_hash(GENESIS_HASH) == Some(genesis))?;
+
+    #[cfg(feature = "alloc")]
+    mining_vectors()?;
+    Ok(())
+}
+
+/// Every backend must compute the same midstate, and the genesis nonce
+/// must turn up through `MiningJob` on each of them, from a window that
+/// starts a few nonces before it.
+#[cfg(feature = "alloc")]
+fn mining_vectors() -> Result<(), SelfTestFailure> {
+    let template = BlockTemplate::new(0, 1738368000, "[]", "0", 6, 0);
+    let expected = split_midstate(MIDSTATE_MESSAGE);
+    for backend in Backend::available() {
+        if with_hasher!(backend, hasher => hasher.midstate(MIDSTATE_MESSAGE)) != expected {
+            return Err(SelfTestFailure { vector: "midstate", backend: Some(backend.name()) });
+        }
+
+        let failure = SelfTestFailure { vector: "genesis-mining", backend: Some(backend.name()) };
+        let mut job = template.mining_job().map_err(|_| failure)?;
+        job.set_backend(backend);
+        let found = job.mine(GENESIS_NONCE - 13, 1, 32);
+        if !found.is_some_and(|r| r.nonce == GENESIS_NONCE && r.hash_hex == GENESIS_HASH) {
+            return Err(failure);
+        }
+    }
+    Ok(())
+}
+
+/// What `benchmark` measured.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Benchmark {
+    pub hashes: u64,
+    pub elapsed_ms: f64,
+    pub backend: Backend,
+}
+
+#[cfg(feature = "alloc")]
+impl Benchmark {
+    pub fn hashes_per_sec(&self) -> f64 {
+        if self.elapsed_ms > 0.0 {
+            self.hashes as f64 * 1000.0 / self.elapsed_ms
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Mine a typical post-fork template for about `duration_ms` on this
+/// thread, with the default backend, against a target nothing meets.
+/// `clock` returns milliseconds from any fixed origin.
+#[cfg(feature = "alloc")]
+pub fn benchmark(duration_ms: f64, mut clock: impl FnMut() -> f64) -> Benchmark {
+    let merkle_root = "7f83b1657ff1fc53b92dc18148a1d65dfc2d4b1fa3d677284addd200126d9069";
+    let previous_hash = "00000014a2f0c6b5e2d3f1a8c7b6e5d4c3b2a1908f7e6d5c4b3a29180f7e6d5c";
+    let template = BlockTemplate::new(6001, 1738368000, merkle_root, previous_hash, 6, 24);
+    let mut job = template.mining_job().expect("mainnet's suffix is just the difficulty");
+    job.set_target(Target::ZERO);
+
+    let began = clock();
+    let timed = job.mine_for(0, 1, duration_ms, None, &mut clock);
+    Benchmark { hashes: timed.hashes, elapsed_ms: clock() - began, backend: job.backend() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test_passes() {
+        assert_eq!(self_test(), Ok(()));
+    }
+
+    #[test]
+    fn test_failure_message() {
+        let failure = SelfTestFailure { vector: "genesis-mining", backend: Some("avx2") };
+        assert_eq!(failure.to_string(), "self-test vector genesis-mining failed on the avx2 backend");
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_benchmark_runs_for_its_duration() {
+        let start = std::time::Instant::now();
+        let bench = benchmark(30.0, || start.elapsed().as_secs_f64() * 1000.0);
+        assert!(bench.hashes > 0);
+        assert!(bench.elapsed_ms >= 30.0, "{}", bench.elapsed_ms);
+        assert!(bench.hashes_per_sec() > 0.0);
+        assert_eq!(bench.backend, Backend::detect());
+    }
+}
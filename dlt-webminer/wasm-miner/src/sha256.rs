@@ -1,11 +1,25 @@
-/// Custom SHA-256 implementation with midstate support for mining.
-/// Ported from dilithiumcoin/cmd/dilithium-cpu-gpu-miner/sha256.go
-///
-/// The midstate optimization pre-computes SHA-256 state for the fixed prefix
-/// of block data, then only processes the variable nonce + suffix per attempt.
+//! Custom SHA-256 implementation with midstate support for mining.
+//! Ported from dilithiumcoin/cmd/dilithium-cpu-gpu-miner/sha256.go
+//!
+//! The midstate optimization pre-computes SHA-256 state for the fixed prefix
+//! of block data, then only processes the variable nonce + suffix per attempt.
 
 // SHA-256 round constants are inlined directly into the unrolled compression
-// rounds via the sha256_round! macro for maximum performance.
+// rounds via the sha256_round! macro for maximum performance. The table below
+// is only used by the rounds_unrolled! paths (HashJob, SIMD kernel), where it
+// is indexed by literal round numbers and folds to the same constants.
+
+/// SHA-256 round constants.
+pub(crate) const K256: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
 
 /// SHA-256 initial hash values (IV)
 pub const INIT_H: [u32; 8] = [
@@ -15,12 +29,12 @@ pub const INIT_H: [u32; 8] = [
 
 #[inline(always)]
 fn rotr(x: u32, n: u32) -> u32 {
-    (x >> n) | (x << (32 - n))
+    x.rotate_right(n)
 }
 
 /// Load a big-endian u32 from a byte slice at offset `i*4`.
 #[inline(always)]
-fn load_be32(block: &[u8], i: usize) -> u32 {
+pub(crate) fn load_be32(block: &[u8], i: usize) -> u32 {
     let off = i * 4;
     (block[off] as u32) << 24
         | (block[off + 1] as u32) << 16
@@ -30,13 +44,13 @@ fn load_be32(block: &[u8], i: usize) -> u32 {
 
 /// sigma0 for message schedule expansion
 #[inline(always)]
-fn sigma0(x: u32) -> u32 {
+pub(crate) fn sigma0(x: u32) -> u32 {
     rotr(x, 7) ^ rotr(x, 18) ^ (x >> 3)
 }
 
 /// sigma1 for message schedule expansion
 #[inline(always)]
-fn sigma1(x: u32) -> u32 {
+pub(crate) fn sigma1(x: u32) -> u32 {
     rotr(x, 17) ^ rotr(x, 19) ^ (x >> 10)
 }
 
@@ -200,7 +214,7 @@ pub fn sha256_block(h: &mut [u32; 8], block: &[u8]) {
 }
 
 /// SHA256State holds the intermediate SHA-256 hash state.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Sha256State {
     pub h: [u32; 8],
     pub len: u64, // total bytes processed into this state
@@ -226,33 +240,37 @@ impl Sha256State {
     }
 }
 
+impl Default for Sha256State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Compute midstate from prefix data.
 /// Returns (h[0..8], processed_byte_count, remaining_tail_bytes).
-pub fn compute_midstate(prefix: &[u8]) -> (Sha256State, Vec<u8>) {
+#[cfg(feature = "alloc")]
+pub fn compute_midstate(prefix: &[u8]) -> (Sha256State, alloc::vec::Vec<u8>) {
+    let (state, tail) = split_midstate(prefix);
+    (state, tail.to_vec())
+}
+
+/// `compute_midstate` without the copy: the tail is the unhashed end of
+/// `prefix` itself.
+pub fn split_midstate(prefix: &[u8]) -> (Sha256State, &[u8]) {
     let mut state = Sha256State::new();
     let full_blocks = (prefix.len() / 64) * 64;
     if full_blocks > 0 {
         state.process_blocks(&prefix[..full_blocks]);
     }
-    let tail = prefix[full_blocks..].to_vec();
-    (state, tail)
+    (state, &prefix[full_blocks..])
 }
 
-/// Internal: compute SHA-256 from midstate, return the raw u32 state.
-/// This avoids serialization overhead -- callers can check difficulty on u32 words directly.
+/// Build the padded final block(s) for the last `rem.len() < 64` bytes of a
+/// message that is `total_len` bytes long in total.
+/// Returns the stack buffer and the padded length (64 or 128).
 #[inline(always)]
-fn mine_hash_raw(mid_h: [u32; 8], remaining: &[u8], midstate_len: u64) -> [u32; 8] {
-    let mut h = mid_h;
-    let mut rem = remaining;
-    let total_len = midstate_len + rem.len() as u64;
-
-    // Process any complete 64-byte blocks in remaining
-    while rem.len() >= 64 {
-        sha256_block(&mut h, &rem[..64]);
-        rem = &rem[64..];
-    }
-
-    // Build final padded block(s) on stack
+pub(crate) fn pad_final(rem: &[u8], total_len: u64) -> ([u8; 128], usize) {
+    debug_assert!(rem.len() < 64);
     let mut buf = [0u8; 128];
     let rlen = rem.len();
     buf[..rlen].copy_from_slice(rem);
@@ -270,6 +288,26 @@ fn mine_hash_raw(mid_h: [u32; 8], remaining: &[u8], midstate_len: u64) -> [u32;
     buf[pad_len - 2] = (bit_len >> 8) as u8;
     buf[pad_len - 1] = bit_len as u8;
 
+    (buf, pad_len)
+}
+
+/// Internal: compute SHA-256 from midstate, return the raw u32 state.
+/// This avoids serialization overhead -- callers can check difficulty on u32 words directly.
+#[inline(always)]
+fn mine_hash_raw(mid_h: [u32; 8], remaining: &[u8], midstate_len: u64) -> [u32; 8] {
+    let mut h = mid_h;
+    let mut rem = remaining;
+    let total_len = midstate_len + rem.len() as u64;
+
+    // Process any complete 64-byte blocks in remaining
+    while rem.len() >= 64 {
+        sha256_block(&mut h, &rem[..64]);
+        rem = &rem[64..];
+    }
+
+    // Build final padded block(s) on stack
+    let (buf, pad_len) = pad_final(rem, total_len);
+
     sha256_block(&mut h, &buf[..64]);
     if pad_len == 128 {
         sha256_block(&mut h, &buf[64..128]);
@@ -323,6 +361,18 @@ pub fn meets_difficulty_u32(h: &[u32; 8], bits: u32) -> bool {
     true
 }
 
+/// Leading-word pre-check: the part of `meets_difficulty_u32` that only
+/// needs h[0]. A hash can only meet `bits` if this passes, and for
+/// `bits <= 32` it is the whole check.
+#[inline(always)]
+pub fn meets_difficulty_leading(h0: u32, bits: u32) -> bool {
+    match bits {
+        0 => true,
+        1..=31 => h0 >> (32 - bits) == 0,
+        _ => h0 == 0,
+    }
+}
+
 /// Compute SHA-256 from a midstate and remaining data.
 /// This is the hot-path function called for every nonce attempt.
 ///
@@ -334,16 +384,401 @@ pub fn mine_hash(mid_h: [u32; 8], remaining: &[u8], midstate_len: u64) -> [u8; 3
     hash_to_bytes(&h)
 }
 
-/// Mining-optimized: compute hash and check difficulty without serialization.
-/// Returns Some([u8;32]) only if the hash meets the required difficulty.
-/// For the ~99.99% of hashes that fail, no byte serialization is done.
+/// All 64 rounds with the same unrolled register rotation as `sha256_block`,
+/// generic over the round macro so the SIMD kernel can share it.
+/// Round `N` only runs if `$from <= N < $to`; with const bounds the skipped
+/// rounds compile away, leaving straight-line code for just that range.
+macro_rules! rounds_unrolled {
+    ($round:ident, $from:expr, $to:expr, $k:expr, $w:expr,
+     $a:ident, $b:ident, $c:ident, $d:ident, $e:ident, $f:ident, $g:ident, $hv:ident) => {
+        if $from <= 0 && 0 < $to { $round!($a, $b, $c, $d, $e, $f, $g, $hv, $k[0], $w[0]); }
+        if $from <= 1 && 1 < $to { $round!($hv, $a, $b, $c, $d, $e, $f, $g, $k[1], $w[1]); }
+        if $from <= 2 && 2 < $to { $round!($g, $hv, $a, $b, $c, $d, $e, $f, $k[2], $w[2]); }
+        if $from <= 3 && 3 < $to { $round!($f, $g, $hv, $a, $b, $c, $d, $e, $k[3], $w[3]); }
+        if $from <= 4 && 4 < $to { $round!($e, $f, $g, $hv, $a, $b, $c, $d, $k[4], $w[4]); }
+        if $from <= 5 && 5 < $to { $round!($d, $e, $f, $g, $hv, $a, $b, $c, $k[5], $w[5]); }
+        if $from <= 6 && 6 < $to { $round!($c, $d, $e, $f, $g, $hv, $a, $b, $k[6], $w[6]); }
+        if $from <= 7 && 7 < $to { $round!($b, $c, $d, $e, $f, $g, $hv, $a, $k[7], $w[7]); }
+
+        if $from <= 8 && 8 < $to { $round!($a, $b, $c, $d, $e, $f, $g, $hv, $k[8], $w[8]); }
+        if $from <= 9 && 9 < $to { $round!($hv, $a, $b, $c, $d, $e, $f, $g, $k[9], $w[9]); }
+        if $from <= 10 && 10 < $to { $round!($g, $hv, $a, $b, $c, $d, $e, $f, $k[10], $w[10]); }
+        if $from <= 11 && 11 < $to { $round!($f, $g, $hv, $a, $b, $c, $d, $e, $k[11], $w[11]); }
+        if $from <= 12 && 12 < $to { $round!($e, $f, $g, $hv, $a, $b, $c, $d, $k[12], $w[12]); }
+        if $from <= 13 && 13 < $to { $round!($d, $e, $f, $g, $hv, $a, $b, $c, $k[13], $w[13]); }
+        if $from <= 14 && 14 < $to { $round!($c, $d, $e, $f, $g, $hv, $a, $b, $k[14], $w[14]); }
+        if $from <= 15 && 15 < $to { $round!($b, $c, $d, $e, $f, $g, $hv, $a, $k[15], $w[15]); }
+
+        if $from <= 16 && 16 < $to { $round!($a, $b, $c, $d, $e, $f, $g, $hv, $k[16], $w[16]); }
+        if $from <= 17 && 17 < $to { $round!($hv, $a, $b, $c, $d, $e, $f, $g, $k[17], $w[17]); }
+        if $from <= 18 && 18 < $to { $round!($g, $hv, $a, $b, $c, $d, $e, $f, $k[18], $w[18]); }
+        if $from <= 19 && 19 < $to { $round!($f, $g, $hv, $a, $b, $c, $d, $e, $k[19], $w[19]); }
+        if $from <= 20 && 20 < $to { $round!($e, $f, $g, $hv, $a, $b, $c, $d, $k[20], $w[20]); }
+        if $from <= 21 && 21 < $to { $round!($d, $e, $f, $g, $hv, $a, $b, $c, $k[21], $w[21]); }
+        if $from <= 22 && 22 < $to { $round!($c, $d, $e, $f, $g, $hv, $a, $b, $k[22], $w[22]); }
+        if $from <= 23 && 23 < $to { $round!($b, $c, $d, $e, $f, $g, $hv, $a, $k[23], $w[23]); }
+
+        if $from <= 24 && 24 < $to { $round!($a, $b, $c, $d, $e, $f, $g, $hv, $k[24], $w[24]); }
+        if $from <= 25 && 25 < $to { $round!($hv, $a, $b, $c, $d, $e, $f, $g, $k[25], $w[25]); }
+        if $from <= 26 && 26 < $to { $round!($g, $hv, $a, $b, $c, $d, $e, $f, $k[26], $w[26]); }
+        if $from <= 27 && 27 < $to { $round!($f, $g, $hv, $a, $b, $c, $d, $e, $k[27], $w[27]); }
+        if $from <= 28 && 28 < $to { $round!($e, $f, $g, $hv, $a, $b, $c, $d, $k[28], $w[28]); }
+        if $from <= 29 && 29 < $to { $round!($d, $e, $f, $g, $hv, $a, $b, $c, $k[29], $w[29]); }
+        if $from <= 30 && 30 < $to { $round!($c, $d, $e, $f, $g, $hv, $a, $b, $k[30], $w[30]); }
+        if $from <= 31 && 31 < $to { $round!($b, $c, $d, $e, $f, $g, $hv, $a, $k[31], $w[31]); }
+
+        if $from <= 32 && 32 < $to { $round!($a, $b, $c, $d, $e, $f, $g, $hv, $k[32], $w[32]); }
+        if $from <= 33 && 33 < $to { $round!($hv, $a, $b, $c, $d, $e, $f, $g, $k[33], $w[33]); }
+        if $from <= 34 && 34 < $to { $round!($g, $hv, $a, $b, $c, $d, $e, $f, $k[34], $w[34]); }
+        if $from <= 35 && 35 < $to { $round!($f, $g, $hv, $a, $b, $c, $d, $e, $k[35], $w[35]); }
+        if $from <= 36 && 36 < $to { $round!($e, $f, $g, $hv, $a, $b, $c, $d, $k[36], $w[36]); }
+        if $from <= 37 && 37 < $to { $round!($d, $e, $f, $g, $hv, $a, $b, $c, $k[37], $w[37]); }
+        if $from <= 38 && 38 < $to { $round!($c, $d, $e, $f, $g, $hv, $a, $b, $k[38], $w[38]); }
+        if $from <= 39 && 39 < $to { $round!($b, $c, $d, $e, $f, $g, $hv, $a, $k[39], $w[39]); }
+
+        if $from <= 40 && 40 < $to { $round!($a, $b, $c, $d, $e, $f, $g, $hv, $k[40], $w[40]); }
+        if $from <= 41 && 41 < $to { $round!($hv, $a, $b, $c, $d, $e, $f, $g, $k[41], $w[41]); }
+        if $from <= 42 && 42 < $to { $round!($g, $hv, $a, $b, $c, $d, $e, $f, $k[42], $w[42]); }
+        if $from <= 43 && 43 < $to { $round!($f, $g, $hv, $a, $b, $c, $d, $e, $k[43], $w[43]); }
+        if $from <= 44 && 44 < $to { $round!($e, $f, $g, $hv, $a, $b, $c, $d, $k[44], $w[44]); }
+        if $from <= 45 && 45 < $to { $round!($d, $e, $f, $g, $hv, $a, $b, $c, $k[45], $w[45]); }
+        if $from <= 46 && 46 < $to { $round!($c, $d, $e, $f, $g, $hv, $a, $b, $k[46], $w[46]); }
+        if $from <= 47 && 47 < $to { $round!($b, $c, $d, $e, $f, $g, $hv, $a, $k[47], $w[47]); }
+
+        if $from <= 48 && 48 < $to { $round!($a, $b, $c, $d, $e, $f, $g, $hv, $k[48], $w[48]); }
+        if $from <= 49 && 49 < $to { $round!($hv, $a, $b, $c, $d, $e, $f, $g, $k[49], $w[49]); }
+        if $from <= 50 && 50 < $to { $round!($g, $hv, $a, $b, $c, $d, $e, $f, $k[50], $w[50]); }
+        if $from <= 51 && 51 < $to { $round!($f, $g, $hv, $a, $b, $c, $d, $e, $k[51], $w[51]); }
+        if $from <= 52 && 52 < $to { $round!($e, $f, $g, $hv, $a, $b, $c, $d, $k[52], $w[52]); }
+        if $from <= 53 && 53 < $to { $round!($d, $e, $f, $g, $hv, $a, $b, $c, $k[53], $w[53]); }
+        if $from <= 54 && 54 < $to { $round!($c, $d, $e, $f, $g, $hv, $a, $b, $k[54], $w[54]); }
+        if $from <= 55 && 55 < $to { $round!($b, $c, $d, $e, $f, $g, $hv, $a, $k[55], $w[55]); }
+
+        if $from <= 56 && 56 < $to { $round!($a, $b, $c, $d, $e, $f, $g, $hv, $k[56], $w[56]); }
+        if $from <= 57 && 57 < $to { $round!($hv, $a, $b, $c, $d, $e, $f, $g, $k[57], $w[57]); }
+        if $from <= 58 && 58 < $to { $round!($g, $hv, $a, $b, $c, $d, $e, $f, $k[58], $w[58]); }
+        if $from <= 59 && 59 < $to { $round!($f, $g, $hv, $a, $b, $c, $d, $e, $k[59], $w[59]); }
+        if $from <= 60 && 60 < $to { $round!($e, $f, $g, $hv, $a, $b, $c, $d, $k[60], $w[60]); }
+        if $from <= 61 && 61 < $to { $round!($d, $e, $f, $g, $hv, $a, $b, $c, $k[61], $w[61]); }
+        if $from <= 62 && 62 < $to { $round!($c, $d, $e, $f, $g, $hv, $a, $b, $k[62], $w[62]); }
+        if $from <= 63 && 63 < $to { $round!($b, $c, $d, $e, $f, $g, $hv, $a, $k[63], $w[63]); }
+    };
+}
+#[cfg(any(all(target_arch = "wasm32", target_feature = "simd128"), target_arch = "x86_64"))]
+pub(crate) use rounds_unrolled;
+
+/// Rounds `START..END` over a full schedule. Takes and returns the working
+/// state in role order `[a, b, c, d, e, f, g, h]`.
 #[inline(always)]
-pub fn mine_hash_check(mid_h: [u32; 8], remaining: &[u8], midstate_len: u64, diff_bits: u32) -> Option<[u8; 32]> {
-    let h = mine_hash_raw(mid_h, remaining, midstate_len);
-    if meets_difficulty_u32(&h, diff_bits) {
-        Some(hash_to_bytes(&h))
-    } else {
-        None
+fn rounds_from<const START: usize, const END: usize>(s: [u32; 8], w: &[u32; 64]) -> [u32; 8] {
+    // In the unrolled sequence, round N sees role `a` in variable (8 - N % 8) % 8,
+    // so rotate the state in to where round START picks it up, and back out
+    // from where round END leaves it.
+    let mut v = s;
+    v.rotate_left(START % 8);
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hv] = v;
+    rounds_unrolled!(sha256_round, START, END, K256, w, a, b, c, d, e, f, g, hv);
+    let mut out = [a, b, c, d, e, f, g, hv];
+    out.rotate_right(END % 8);
+    out
+}
+
+/// The `a` produced by one round, without the rest of the round. For the
+/// final round this is all the leading hash word depends on.
+#[inline(always)]
+fn round_a(s: &[u32; 8], k: u32, w: u32) -> u32 {
+    let [a, b, c, _, e, f, g, hv] = *s;
+    let s1 = rotr(e, 6) ^ rotr(e, 11) ^ rotr(e, 25);
+    let ch = (e & f) ^ (!e & g);
+    let temp1 = hv
+        .wrapping_add(s1)
+        .wrapping_add(ch)
+        .wrapping_add(k)
+        .wrapping_add(w);
+    let s0 = rotr(a, 2) ^ rotr(a, 13) ^ rotr(a, 22);
+    let maj = (a & b) ^ (a & c) ^ (b & c);
+    temp1.wrapping_add(s0.wrapping_add(maj))
+}
+
+/// Run the final round (63) on a working state and feed it forward into `h`.
+#[inline(always)]
+fn finish_last_round(h: &mut [u32; 8], s: [u32; 8], w63: u32) {
+    let s = round_step(s, K256[63], w63);
+    for (hi, si) in h.iter_mut().zip(s.iter()) {
+        *hi = hi.wrapping_add(*si);
+    }
+}
+
+/// The nonce-dependent part of a job's first block, for a job whose first
+/// nonce word is `START`: load words `START..16`, finish the schedule (reusing
+/// the cached partial sums below `START + 15`) and run rounds `START..63`.
+/// Returns the working state before the final round, and `w[63]`.
+///
+/// Every bound is a constant, so each instantiation unrolls into straight-line
+/// code like `sha256_block`.
+#[inline(never)]
+fn job_block0<const START: usize>(state0: [u32; 8], w0: &[u32; 64], block: &[u8]) -> ([u32; 8], u32) {
+    let mut w = *w0;
+    let mut i = START;
+    while i < 16 {
+        w[i] = load_be32(block, i);
+        i += 1;
+    }
+    while i < 64 {
+        if i < START + 15 {
+            w[i] = sigma1(w[i - 2]).wrapping_add(w[i - 7]).wrapping_add(w[i]);
+        } else {
+            schedule!(w, i);
+        }
+        i += 1;
+    }
+    (rounds_from::<START, 63>(state0, &w), w[63])
+}
+
+/// Pick the `job_block0` instantiation for a job's first nonce word (< 16).
+#[inline(always)]
+fn job_block0_dispatch(start: usize, state0: [u32; 8], w0: &[u32; 64], block: &[u8]) -> ([u32; 8], u32) {
+    match start {
+        0 => job_block0::<0>(state0, w0, block),
+        1 => job_block0::<1>(state0, w0, block),
+        2 => job_block0::<2>(state0, w0, block),
+        3 => job_block0::<3>(state0, w0, block),
+        4 => job_block0::<4>(state0, w0, block),
+        5 => job_block0::<5>(state0, w0, block),
+        6 => job_block0::<6>(state0, w0, block),
+        7 => job_block0::<7>(state0, w0, block),
+        8 => job_block0::<8>(state0, w0, block),
+        9 => job_block0::<9>(state0, w0, block),
+        10 => job_block0::<10>(state0, w0, block),
+        11 => job_block0::<11>(state0, w0, block),
+        12 => job_block0::<12>(state0, w0, block),
+        13 => job_block0::<13>(state0, w0, block),
+        14 => job_block0::<14>(state0, w0, block),
+        _ => job_block0::<15>(state0, w0, block),
+    }
+}
+
+/// One compression round on a `[a, b, c, d, e, f, g, h]` working state.
+/// Used for a job's nonce-invariant leading rounds and to finish the final
+/// round once a hash has passed the leading-word check.
+#[inline(always)]
+fn round_step(s: [u32; 8], k: u32, w: u32) -> [u32; 8] {
+    let [a, b, c, mut d, e, f, g, mut hv] = s;
+    sha256_round!(a, b, c, d, e, f, g, hv, k, w);
+    // The macro leaves the new `a` in `hv` and the new `e` in `d`
+    [hv, a, b, c, d, e, f, g]
+}
+
+/// Expand a full 64-word schedule from the 16 block words in `w[..16]`.
+#[inline(always)]
+fn expand_schedule(w: &mut [u32; 64]) {
+    for i in 16..64 {
+        schedule!(w, i);
+    }
+}
+
+/// Largest padded tail a HashJob can hold (5 blocks). Covers a 63-byte
+/// prefix tail, a 20-digit nonce and a suffix of up to
+/// `HASH_JOB_MAX_SUFFIX` bytes.
+pub const HASH_JOB_MAX_PADDED: usize = 320;
+
+/// Longest suffix a HashJob takes whatever the prefix tail and nonce: what
+/// `HASH_JOB_MAX_PADDED` leaves after a 63-byte tail, a 20-character
+/// nonce and 9 bytes of padding.
+pub const HASH_JOB_MAX_SUFFIX: usize = HASH_JOB_MAX_PADDED - 63 - 20 - 9;
+
+/// Blocks a nonce can touch: a tail of at most 63 bytes plus a nonce of at
+/// most 65 digits always ends inside the second block. Bounds the per-nonce
+/// scratch copy.
+pub(crate) const HASH_JOB_NONCE_BYTES: usize = 128;
+
+/// Nonce-invariant precomputation for the final block(s) of a mining hash.
+///
+/// For a fixed template and a fixed nonce length, everything after the
+/// midstate is constant except the nonce digits:
+///
+/// ```text
+///   prefix_tail | nonce | suffix | 0x80 00.. | bit length
+///               ^ nonce_off
+/// ```
+///
+/// Words of the first block before `nonce_off / 4` never change, so the
+/// rounds that consume them are run once here. The schedule terms that only
+/// reach back into those words (`w[i-16] + sigma0(w[i-15])`) are cached, and
+/// blocks that lie entirely after the nonce get their whole schedule cached.
+/// Per nonce, only the affected words and rounds are recomputed.
+///
+/// A job is tied to one nonce length; rebuild it when the digit count changes.
+#[derive(Clone)]
+pub struct HashJob {
+    pub(crate) mid_h: [u32; 8],
+    /// Padded remaining bytes with the nonce digits left as zeros.
+    pub(crate) buf: [u8; HASH_JOB_MAX_PADDED],
+    pub(crate) nonce_off: usize,
+    pub(crate) nonce_len: usize,
+    /// Total padded blocks after the midstate.
+    pub(crate) blocks: usize,
+    /// Blocks `0..nonce_blocks` contain nonce bytes.
+    pub(crate) nonce_blocks: usize,
+    /// First schedule word of block 0 that depends on the nonce.
+    pub(crate) first_word: usize,
+    /// Working state after rounds `0..first_word` of block 0.
+    pub(crate) state0: [u32; 8],
+    /// Block 0 schedule: `w0[..first_word]` are the fixed words, and
+    /// `w0[i]` for `16 <= i < first_word + 15` holds `w[i-16] + sigma0(w[i-15])`.
+    pub(crate) w0: [u32; 64],
+    /// Full schedules for blocks `nonce_blocks..blocks`.
+    pub(crate) w_fixed: [[u32; 64]; HASH_JOB_MAX_PADDED / 64],
+}
+
+impl HashJob {
+    /// Precompute the final-block layout for nonces of `nonce_len` digits.
+    ///
+    /// `prefix_tail` may be longer than a block; whole blocks are folded into
+    /// the midstate here. Panics if the padded message exceeds
+    /// `HASH_JOB_MAX_PADDED` bytes; a suffix of at most
+    /// `HASH_JOB_MAX_SUFFIX` bytes always fits.
+    pub fn new(mid_h: [u32; 8], midstate_len: u64, prefix_tail: &[u8], nonce_len: usize, suffix: &[u8]) -> Self {
+        let mut mid_h = mid_h;
+        let mut midstate_len = midstate_len;
+        let mut tail = prefix_tail;
+        while tail.len() >= 64 {
+            sha256_block(&mut mid_h, &tail[..64]);
+            midstate_len += 64;
+            tail = &tail[64..];
+        }
+
+        let nonce_off = tail.len();
+        let rem_len = nonce_off + nonce_len + suffix.len();
+        let blocks = (rem_len + 9).div_ceil(64);
+        assert!(blocks * 64 <= HASH_JOB_MAX_PADDED, "mining tail too long for HashJob");
+
+        // Lay out the padded message with a zeroed nonce
+        let mut buf = [0u8; HASH_JOB_MAX_PADDED];
+        buf[..nonce_off].copy_from_slice(tail);
+        buf[nonce_off + nonce_len..rem_len].copy_from_slice(suffix);
+        buf[rem_len] = 0x80;
+        let bit_len = (midstate_len + rem_len as u64) * 8;
+        buf[blocks * 64 - 8..blocks * 64].copy_from_slice(&bit_len.to_be_bytes());
+
+        let nonce_end = nonce_off + nonce_len;
+        let nonce_blocks = nonce_end.div_ceil(64).max(1);
+        assert!(nonce_blocks * 64 <= HASH_JOB_NONCE_BYTES, "nonce too long for HashJob");
+        let first_word = nonce_off / 4;
+
+        // Block 0: fixed words, partial schedule sums and leading rounds
+        let mut w0 = [0u32; 64];
+        for (i, word) in w0.iter_mut().enumerate().take(first_word) {
+            *word = load_be32(&buf, i);
+        }
+        for i in 16..first_word + 15 {
+            w0[i] = w0[i - 16].wrapping_add(sigma0(w0[i - 15]));
+        }
+        let mut state0 = mid_h;
+        for (i, &word) in w0.iter().enumerate().take(first_word) {
+            state0 = round_step(state0, K256[i], word);
+        }
+
+        // Blocks after the nonce: fully fixed schedules
+        let mut w_fixed = [[0u32; 64]; HASH_JOB_MAX_PADDED / 64];
+        for (b, w) in w_fixed.iter_mut().enumerate().take(blocks).skip(nonce_blocks) {
+            for (i, word) in w.iter_mut().enumerate().take(16) {
+                *word = load_be32(&buf[b * 64..], i);
+            }
+            expand_schedule(w);
+        }
+
+        HashJob {
+            mid_h,
+            buf,
+            nonce_off,
+            nonce_len,
+            blocks,
+            nonce_blocks,
+            first_word,
+            state0,
+            w0,
+            w_fixed,
+        }
+    }
+
+    /// Number of nonce digits this job was laid out for.
+    #[inline(always)]
+    pub fn nonce_len(&self) -> usize {
+        self.nonce_len
+    }
+
+    /// Run everything up to, but not including, round 63 of the last block.
+    /// Returns the chaining value going into the last block, the working
+    /// state before its final round, and its `w[63]`.
+    #[inline(always)]
+    fn run_to_last_round(&self, nonce: &[u8]) -> ([u32; 8], [u32; 8], u32) {
+        debug_assert_eq!(nonce.len(), self.nonce_len);
+
+        // Only the blocks that carry nonce bytes need a writable copy
+        let mut block = [0u8; HASH_JOB_NONCE_BYTES];
+        let nonce_bytes = self.nonce_blocks * 64;
+        block[..nonce_bytes].copy_from_slice(&self.buf[..nonce_bytes]);
+        block[self.nonce_off..self.nonce_off + self.nonce_len].copy_from_slice(nonce);
+
+        // Block 0: reuse fixed words, partial sums and leading rounds
+        let mut h = self.mid_h;
+        let (mut s, mut w63) = job_block0_dispatch(self.first_word, self.state0, &self.w0, &block);
+
+        // Later blocks: those the nonce spills into, then nonce-free ones
+        // with cached schedules
+        let mut w_nonce = [0u32; 64];
+        for b in 1..self.blocks {
+            finish_last_round(&mut h, s, w63);
+            let w = if b < self.nonce_blocks {
+                for (i, word) in w_nonce.iter_mut().enumerate().take(16) {
+                    *word = load_be32(&block[b * 64..], i);
+                }
+                expand_schedule(&mut w_nonce);
+                &w_nonce
+            } else {
+                &self.w_fixed[b]
+            };
+            s = rounds_from::<0, 63>(h, w);
+            w63 = w[63];
+        }
+
+        (h, s, w63)
+    }
+
+    /// Compute the raw u32 hash state for one nonce.
+    /// `nonce` must be exactly `nonce_len()` bytes.
+    #[inline(always)]
+    pub fn hash(&self, nonce: &[u8]) -> [u32; 8] {
+        let (mut h, s, w63) = self.run_to_last_round(nonce);
+        finish_last_round(&mut h, s, w63);
+        h
+    }
+
+    /// Just h[0] for one nonce: everything a difficulty check of up to 32
+    /// bits needs, at the cost of `check`'s rejection path.
+    #[inline(always)]
+    pub fn leading_word(&self, nonce: &[u8]) -> u32 {
+        let (h, s, w63) = self.run_to_last_round(nonce);
+        h[0].wrapping_add(round_a(&s, K256[63], w63))
+    }
+
+    /// Mining-optimized: hash one nonce and check difficulty, returning the
+    /// raw state only if it meets `diff_bits`.
+    ///
+    /// h[0] is the last word SHA-256 produces (the `a` of round 63), so the
+    /// early exit sits right at the end: for a failing nonce, round 63's `e`
+    /// update and the feed-forward of h[1..8] are skipped. Only candidates
+    /// whose leading word passes go on to the full state and full check.
+    #[inline(always)]
+    pub fn check(&self, nonce: &[u8], diff_bits: u32) -> Option<[u32; 8]> {
+        let (mut h, s, w63) = self.run_to_last_round(nonce);
+        let h0 = h[0].wrapping_add(round_a(&s, K256[63], w63));
+        if !meets_difficulty_leading(h0, diff_bits) {
+            return None;
+        }
+        finish_last_round(&mut h, s, w63);
+        if meets_difficulty_u32(&h, diff_bits) { Some(h) } else { None }
     }
 }
 
@@ -357,11 +792,71 @@ pub fn sha256(data: &[u8]) -> [u8; 32] {
     mine_hash(state.h, &data[full_blocks..], state.len)
 }
 
+/// SHA-256 of a 32-byte digest given as its state words: the second pass
+/// of double SHA-256, with no byte round trip.
+pub fn sha256_of_state(digest: &[u32; 8]) -> [u32; 8] {
+    let mut block = [0u8; 64];
+    block[..32].copy_from_slice(&hash_to_bytes(digest));
+    block[32] = 0x80;
+    // 256-bit message length
+    block[62] = 0x01;
+    let mut h = INIT_H;
+    sha256_block(&mut h, &block);
+    h
+}
+
+/// Incremental SHA-256 over data fed in pieces, for hashing a message
+/// without first assembling it in a buffer.
+#[derive(Clone, Debug)]
+pub struct Sha256Hasher {
+    state: Sha256State,
+    // Bytes not yet making up a whole block
+    buf: [u8; 64],
+    buf_len: usize,
+}
+
+impl Sha256Hasher {
+    pub fn new() -> Self {
+        Sha256Hasher { state: Sha256State::new(), buf: [0; 64], buf_len: 0 }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        if self.buf_len > 0 {
+            let take = data.len().min(64 - self.buf_len);
+            self.buf[self.buf_len..self.buf_len + take].copy_from_slice(&data[..take]);
+            self.buf_len += take;
+            data = &data[take..];
+            if self.buf_len < 64 {
+                return;
+            }
+            let block = self.buf;
+            self.state.process_blocks(&block);
+            self.buf_len = 0;
+        }
+        let full_blocks = (data.len() / 64) * 64;
+        self.state.process_blocks(&data[..full_blocks]);
+        let tail = &data[full_blocks..];
+        self.buf[..tail.len()].copy_from_slice(tail);
+        self.buf_len = tail.len();
+    }
+
+    pub fn finalize(self) -> [u8; 32] {
+        mine_hash(self.state.h, &self.buf[..self.buf_len], self.state.len)
+    }
+}
+
+impl Default for Sha256Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
+    #[cfg(feature = "alloc")]
     fn test_sha256_empty() {
         let hash = sha256(b"");
         let hex = crate::utils::hash_to_hex(&hash);
@@ -369,6 +864,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "alloc")]
     fn test_sha256_abc() {
         let hash = sha256(b"abc");
         let hex = crate::utils::hash_to_hex(&hash);
@@ -376,6 +872,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "alloc")]
     fn test_midstate_equivalence() {
         // Verify that midstate-based hash matches full hash
         let data = b"1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef_extra_nonce_data_here";
@@ -386,9 +883,111 @@ mod tests {
 
         assert_eq!(full_hash, midstate_hash);
     }
+
+    #[test]
+    fn test_split_midstate() {
+        // The no-alloc form borrows the tail instead of copying it
+        let data = b"1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef_extra_nonce_data_here";
+        let (state, tail) = split_midstate(data);
+        assert_eq!(state.len, 64);
+        assert_eq!(tail, &data[64..]);
+        assert_eq!(mine_hash(state.h, tail, state.len), sha256(data));
+
+        let (state, tail) = split_midstate(&data[..64]);
+        assert_eq!((state.len, tail.len()), (64, 0));
+        let (state, tail) = split_midstate(b"abc");
+        assert_eq!((state, tail), (Sha256State::new(), b"abc".as_ref()));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_hash_job_matches_mine_hash() {
+        // Every tail length (so the first nonce word moves through the whole
+        // block), short and 19-digit nonces, and suffixes that push the
+        // padding into a second or third block.
+        let prefix: Vec<u8> = (0..200u32).map(|i| b'0' + (i * 7 % 75) as u8).collect();
+        for prefix_len in 64..128 {
+            let (state, tail) = compute_midstate(&prefix[..prefix_len]);
+            for &nonce in &[b"7".as_ref(), b"5892535", b"9223372036854775807"] {
+                for suffix in [b"6".as_ref(), &prefix[..40], &prefix[..90]] {
+                    let job = HashJob::new(state.h, state.len, &tail, nonce.len(), suffix);
+                    let mut remaining = tail.clone();
+                    remaining.extend_from_slice(nonce);
+                    remaining.extend_from_slice(suffix);
+                    assert_eq!(hash_to_bytes(&job.hash(nonce)), mine_hash(state.h, &remaining, state.len),
+                        "prefix {} nonce {} suffix {}", prefix_len, nonce.len(), suffix.len());
+                    assert_eq!(job.leading_word(nonce), job.hash(nonce)[0]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_early_reject_matches_full_check() {
+        // The leading-word early exit must accept exactly the hashes the full
+        // state check accepts: single- and two-block jobs, every low bit count.
+        for prefix in [b"60011738368000abc".as_ref(), &[b'x'; 118]] {
+            let (state, tail) = compute_midstate(prefix);
+            let job = HashJob::new(state.h, state.len, &tail, 4, b"6");
+            let mut found = 0;
+            for n in 1000..3000u32 {
+                let nonce = n.to_string();
+                let full = job.hash(nonce.as_bytes());
+                for bits in [0u32, 1, 3, 6, 9, 33] {
+                    let expected = meets_difficulty_u32(&full, bits);
+                    assert_eq!(job.check(nonce.as_bytes(), bits), expected.then_some(full));
+                    found += expected as u32;
+                }
+            }
+            assert!(found > 2000, "expected some low-difficulty hits");
+        }
+    }
+
+    #[test]
+    fn test_meets_difficulty_leading() {
+        let mut h = [0xFFFF_FFFFu32; 8];
+        h[0] = 0x0000_FFFF;
+        for bits in 0..=64 {
+            // Never rejects a hash the full check would accept
+            if meets_difficulty_u32(&h, bits) {
+                assert!(meets_difficulty_leading(h[0], bits));
+            }
+        }
+        assert!(meets_difficulty_leading(h[0], 16));
+        assert!(!meets_difficulty_leading(h[0], 17));
+        assert!(!meets_difficulty_leading(h[0], 40));
+        assert!(meets_difficulty_leading(0, 40));
+    }
+
+    #[test]
+    fn test_hash_job_long_tail_folds_into_midstate() {
+        // A tail of a block or more (e.g. an un-midstated prefix) still works
+        let prefix = b"0173836800012ab34cd56ef7890ab12cd34ef567890abcdef0123456789abcdef00000abc";
+        let job = HashJob::new(INIT_H, 0, prefix, 4, b"6");
+        let mut data = prefix.to_vec();
+        data.extend_from_slice(b"12346");
+        assert_eq!(hash_to_bytes(&job.hash(b"1234")), sha256(&data));
+    }
+
+    #[test]
+    fn test_hasher_matches_one_shot() {
+        let data: Vec<u8> = (0..300u32).map(|i| (i * 31 % 251) as u8).collect();
+        for split in [0, 1, 55, 63, 64, 65, 128, 200, 300] {
+            for step in [1, 7, 64, 300] {
+                let mut hasher = Sha256Hasher::new();
+                hasher.update(&data[..split]);
+                for piece in data[split..].chunks(step) {
+                    hasher.update(piece);
+                }
+                assert_eq!(hasher.finalize(), sha256(&data), "split {} step {}", split, step);
+            }
+        }
+        assert_eq!(Sha256Hasher::new().finalize(), sha256(b""));
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod genesis_test {
     use super::*;
     use crate::utils::{hash_to_hex, write_i64, write_i32};
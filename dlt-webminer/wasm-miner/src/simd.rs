@@ -0,0 +1,252 @@
+//! 4-lane SHA-256 built on WASM SIMD128.
+//!
+//! Four independent messages of the same length are interleaved across the
+//! u32 lanes of a `v128`, so one pass of the compression function hashes four
+//! nonces. Lane `i` of every vector belongs to message `i`.
+//!
+//! Only compiled for `wasm32` with `+simd128` (see `.cargo/config.toml`);
+//! other targets use the scalar path in `sha256`.
+
+use core::arch::wasm32::*;
+
+use crate::sha256::{rounds_unrolled, HashJob, HASH_JOB_NONCE_BYTES, K256};
+
+#[inline(always)]
+fn rotr(x: v128, n: u32) -> v128 {
+    v128_or(u32x4_shr(x, n), u32x4_shl(x, 32 - n))
+}
+
+#[inline(always)]
+fn sigma0(x: v128) -> v128 {
+    v128_xor(v128_xor(rotr(x, 7), rotr(x, 18)), u32x4_shr(x, 3))
+}
+
+#[inline(always)]
+fn sigma1(x: v128) -> v128 {
+    v128_xor(v128_xor(rotr(x, 17), rotr(x, 19)), u32x4_shr(x, 10))
+}
+
+/// Load big-endian word `i` from each of the four lane blocks.
+#[inline(always)]
+fn load_be32_x4(blocks: &[&[u8]; 4], i: usize) -> v128 {
+    let off = i * 4;
+    let word = |b: &[u8]| u32::from_be_bytes([b[off], b[off + 1], b[off + 2], b[off + 3]]);
+    u32x4(word(blocks[0]), word(blocks[1]), word(blocks[2]), word(blocks[3]))
+}
+
+/// One compression round on four lanes. Same register rotation scheme as the
+/// scalar `sha256_round!`.
+macro_rules! sha256_round_x4 {
+    ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr, $f:expr, $g:expr, $h:expr, $k:expr, $w:expr) => {{
+        let s1 = v128_xor(v128_xor(rotr($e, 6), rotr($e, 11)), rotr($e, 25));
+        let ch = v128_bitselect($f, $g, $e);
+        let temp1 = u32x4_add(
+            u32x4_add(u32x4_add($h, s1), ch),
+            u32x4_add(u32x4_splat($k), $w),
+        );
+        let s0 = v128_xor(v128_xor(rotr($a, 2), rotr($a, 13)), rotr($a, 22));
+        let maj = v128_bitselect($b, $a, v128_xor($a, $c));
+        let temp2 = u32x4_add(s0, maj);
+        $d = u32x4_add($d, temp1);
+        $h = u32x4_add(temp1, temp2);
+    }};
+}
+
+/// Rounds `START..END` over a full 4-lane schedule, with the working state
+/// in role order. Mirrors `sha256::rounds_from`.
+///
+/// Kept out of line: each instantiation is its own function so debug builds
+/// stay within engine limits on locals and stack frame size.
+#[inline(never)]
+fn rounds_from_x4<const START: usize, const END: usize>(s: [v128; 8], w: &[v128; 64]) -> [v128; 8] {
+    let mut v = s;
+    v.rotate_left(START % 8);
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hv] = v;
+    rounds_unrolled!(sha256_round_x4, START, END, K256, w, a, b, c, d, e, f, g, hv);
+    let mut out = [a, b, c, d, e, f, g, hv];
+    out.rotate_right(END % 8);
+    out
+}
+
+/// Block 0 of a job: rounds `start..63` (`start < 16`) with the matching
+/// instantiation.
+#[inline(always)]
+fn rounds_dispatch_x4(s: [v128; 8], w: &[v128; 64], start: usize) -> [v128; 8] {
+    match start {
+        0 => rounds_from_x4::<0, 63>(s, w),
+        1 => rounds_from_x4::<1, 63>(s, w),
+        2 => rounds_from_x4::<2, 63>(s, w),
+        3 => rounds_from_x4::<3, 63>(s, w),
+        4 => rounds_from_x4::<4, 63>(s, w),
+        5 => rounds_from_x4::<5, 63>(s, w),
+        6 => rounds_from_x4::<6, 63>(s, w),
+        7 => rounds_from_x4::<7, 63>(s, w),
+        8 => rounds_from_x4::<8, 63>(s, w),
+        9 => rounds_from_x4::<9, 63>(s, w),
+        10 => rounds_from_x4::<10, 63>(s, w),
+        11 => rounds_from_x4::<11, 63>(s, w),
+        12 => rounds_from_x4::<12, 63>(s, w),
+        13 => rounds_from_x4::<13, 63>(s, w),
+        14 => rounds_from_x4::<14, 63>(s, w),
+        _ => rounds_from_x4::<15, 63>(s, w),
+    }
+}
+
+/// One full round on a role-ordered 4-lane state.
+#[inline(always)]
+fn round_step_x4(s: [v128; 8], k: u32, w: v128) -> [v128; 8] {
+    let [a, b, c, mut d, e, f, g, mut hv] = s;
+    sha256_round_x4!(a, b, c, d, e, f, g, hv, k, w);
+    // The macro leaves the new `a` in `hv` and the new `e` in `d`
+    [hv, a, b, c, d, e, f, g]
+}
+
+/// The `a` produced by one round -- all the leading hash word needs from
+/// the final round.
+#[inline(always)]
+fn round_a_x4(s: &[v128; 8], k: u32, w: v128) -> v128 {
+    let [a, b, c, _, e, f, g, hv] = *s;
+    let s1 = v128_xor(v128_xor(rotr(e, 6), rotr(e, 11)), rotr(e, 25));
+    let ch = v128_bitselect(f, g, e);
+    let temp1 = u32x4_add(u32x4_add(u32x4_add(hv, s1), ch), u32x4_add(u32x4_splat(k), w));
+    let s0 = v128_xor(v128_xor(rotr(a, 2), rotr(a, 13)), rotr(a, 22));
+    let maj = v128_bitselect(b, a, v128_xor(a, c));
+    u32x4_add(temp1, u32x4_add(s0, maj))
+}
+
+/// Run the final round on a 4-lane working state and feed it forward.
+#[inline(always)]
+fn finish_last_round_x4(h: &mut [v128; 8], s: [v128; 8], w63: v128) {
+    let s = round_step_x4(s, K256[63], w63);
+    add_state_x4(h, s);
+}
+
+/// Expand the 4-lane schedule from `w[..16]`.
+#[inline(always)]
+fn expand_schedule_x4(w: &mut [v128; 64], from: usize) {
+    for i in from..64 {
+        w[i] = u32x4_add(
+            u32x4_add(sigma1(w[i - 2]), w[i - 7]),
+            u32x4_add(sigma0(w[i - 15]), w[i - 16]),
+        );
+    }
+}
+
+#[inline(always)]
+fn add_state_x4(h: &mut [v128; 8], s: [v128; 8]) {
+    for (hi, si) in h.iter_mut().zip(s.iter()) {
+        *hi = u32x4_add(*hi, *si);
+    }
+}
+
+#[inline(always)]
+fn splat_state(h: &[u32; 8]) -> [v128; 8] {
+    [
+        u32x4_splat(h[0]), u32x4_splat(h[1]),
+        u32x4_splat(h[2]), u32x4_splat(h[3]),
+        u32x4_splat(h[4]), u32x4_splat(h[5]),
+        u32x4_splat(h[6]), u32x4_splat(h[7]),
+    ]
+}
+
+/// 4-lane `HashJob::run_to_last_round`: everything up to round 63 of the
+/// last block. Returns the chaining value into the last block, the working
+/// state before its final round, and its `w[63]`.
+#[inline(always)]
+fn run_job_to_last_round_x4(job: &HashJob, nonces: [&[u8]; 4]) -> ([v128; 8], [v128; 8], v128) {
+    debug_assert!(nonces.iter().all(|n| n.len() == job.nonce_len));
+
+    // Per-lane copies of the blocks that carry nonce bytes
+    let nonce_bytes = job.nonce_blocks * 64;
+    let mut blocks = [[0u8; HASH_JOB_NONCE_BYTES]; 4];
+    for (block, nonce) in blocks.iter_mut().zip(nonces.iter()) {
+        block[..nonce_bytes].copy_from_slice(&job.buf[..nonce_bytes]);
+        block[job.nonce_off..job.nonce_off + job.nonce_len].copy_from_slice(nonce);
+    }
+    let lanes = [&blocks[0][..], &blocks[1][..], &blocks[2][..], &blocks[3][..]];
+
+    // Block 0: splat the fixed words and partial sums, load the rest per lane
+    let mut w = [u32x4_splat(0); 64];
+    for (i, word) in w.iter_mut().enumerate().take(job.first_word) {
+        *word = u32x4_splat(job.w0[i]);
+    }
+    for (i, word) in w.iter_mut().enumerate().take(16).skip(job.first_word) {
+        *word = load_be32_x4(&lanes, i);
+    }
+    let split = (job.first_word + 15).max(16);
+    for i in 16..split {
+        w[i] = u32x4_add(
+            u32x4_add(sigma1(w[i - 2]), w[i - 7]),
+            u32x4_splat(job.w0[i]),
+        );
+    }
+    expand_schedule_x4(&mut w, split);
+
+    let mut h = splat_state(&job.mid_h);
+    let mut s = rounds_dispatch_x4(splat_state(&job.state0), &w, job.first_word);
+    let mut w63 = w[63];
+
+    for b in 1..job.blocks {
+        finish_last_round_x4(&mut h, s, w63);
+        if b < job.nonce_blocks {
+            let off = b * 64;
+            let lane_blocks = [&lanes[0][off..], &lanes[1][off..], &lanes[2][off..], &lanes[3][off..]];
+            for (i, word) in w.iter_mut().enumerate().take(16) {
+                *word = load_be32_x4(&lane_blocks, i);
+            }
+            expand_schedule_x4(&mut w, 16);
+        } else {
+            for (wv, &word) in w.iter_mut().zip(job.w_fixed[b].iter()) {
+                *wv = u32x4_splat(word);
+            }
+        }
+        s = rounds_from_x4::<0, 63>(h, &w);
+        w63 = w[63];
+    }
+
+    (h, s, w63)
+}
+
+/// `HashJob::leading_word` for four nonces at once.
+#[inline(always)]
+pub fn leading_words_x4(job: &HashJob, nonces: [&[u8]; 4]) -> [u32; 4] {
+    let (h, s, w63) = run_job_to_last_round_x4(job, nonces);
+    let h0 = u32x4_add(h[0], round_a_x4(&s, K256[63], w63));
+    [
+        u32x4_extract_lane::<0>(h0),
+        u32x4_extract_lane::<1>(h0),
+        u32x4_extract_lane::<2>(h0),
+        u32x4_extract_lane::<3>(h0),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leading_words_x4_match_scalar() {
+        let suffix = b"6";
+        for tail_len in [0usize, 3, 17, 40, 55, 63] {
+            let tail: Vec<u8> = (0..tail_len).map(|i| b'a' + (i % 26) as u8).collect();
+            for nonce_len in [1usize, 7, 12, 19] {
+                let job = HashJob::new(crate::sha256::INIT_H, 128, &tail, nonce_len, suffix);
+                let mut nonces = [[0u8; 20]; 4];
+                for (lane, n) in nonces.iter_mut().enumerate() {
+                    for (j, d) in n.iter_mut().enumerate().take(nonce_len) {
+                        *d = b'0' + ((j * 3 + lane) % 10) as u8;
+                    }
+                }
+                let lanes = [
+                    &nonces[0][..nonce_len], &nonces[1][..nonce_len],
+                    &nonces[2][..nonce_len], &nonces[3][..nonce_len],
+                ];
+                let lead = leading_words_x4(&job, lanes);
+                for lane in 0..4 {
+                    assert_eq!(lead[lane], job.hash(lanes[lane])[0],
+                        "lane {} mismatch for tail {} nonce {}", lane, tail_len, nonce_len);
+                }
+            }
+        }
+    }
+}
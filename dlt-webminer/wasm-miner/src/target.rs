@@ -0,0 +1,263 @@
+//! Proof-of-work target — a 256-bit threshold a hash must not exceed.
+//!
+//! Leading-zero-bit difficulty can only step in powers of two. A full target
+//! can sit anywhere in between, which is what pool vardiff needs. Whole bit
+//! counts (and the legacy hex-digit `Difficulty`) map onto it exactly:
+//! `bits` leading zeros is the target `2^(256 - bits) - 1`.
+
+use core::fmt;
+
+/// A hash meets a target if, read as a 256-bit big-endian integer, it is
+/// less than or equal to it.
+///
+/// Stored as big-endian u32 words, the same layout as a raw SHA-256 state,
+/// so the check is a word-by-word compare with no byte conversion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Target([u32; 8]);
+
+// 2^256, built from its exponent since core has no powi
+const TWO_POW_256: f64 = f64::from_bits((1023 + 256) << 52);
+
+/// Why a target string didn't parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseTargetError {
+    /// Not exactly 64 characters.
+    Length,
+    InvalidDigit,
+}
+
+impl fmt::Display for ParseTargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ParseTargetError::Length => "target must be 64 hex digits",
+            ParseTargetError::InvalidDigit => "target contains a non-hex character",
+        })
+    }
+}
+
+impl Target {
+    /// Every hash meets it (difficulty 1, zero bits).
+    pub const MAX: Target = Target([u32::MAX; 8]);
+
+    /// Only the all-zero hash meets it.
+    pub const ZERO: Target = Target([0; 8]);
+
+    pub fn from_words(words: [u32; 8]) -> Self {
+        Target(words)
+    }
+
+    pub fn words(&self) -> &[u32; 8] {
+        &self.0
+    }
+
+    pub fn from_bytes(bytes: &[u8; 32]) -> Self {
+        Target(core::array::from_fn(|i| {
+            u32::from_be_bytes([bytes[i * 4], bytes[i * 4 + 1], bytes[i * 4 + 2], bytes[i * 4 + 3]])
+        }))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(self.0.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Target met by hashes with at least `bits` leading zero bits,
+    /// matching `meets_difficulty_bytes`. 256 and above leave `ZERO`.
+    pub fn from_bits(bits: u32) -> Self {
+        if bits >= 256 {
+            return Target::ZERO;
+        }
+        let full = (bits / 32) as usize;
+        let mut words = [u32::MAX; 8];
+        words[..full].fill(0);
+        words[full] = u32::MAX >> (bits % 32);
+        Target(words)
+    }
+
+    /// Target for the legacy `Difficulty` field: required leading zero hex
+    /// digits, i.e. 4 bits each. Negative values count as 0.
+    pub fn from_legacy_difficulty(difficulty: i32) -> Self {
+        Target::from_bits(difficulty.max(0) as u32 * 4)
+    }
+
+    /// Target for a pool-style floating difficulty: `MAX / difficulty`, so
+    /// difficulty 1 is `MAX` and difficulty `2^n` is exactly
+    /// `from_bits(n)`. In between, each step is as fine as an f64 allows.
+    ///
+    /// Returns None for NaN, infinite, zero or negative difficulty.
+    /// Difficulties below 1 give `MAX`.
+    pub fn from_difficulty(difficulty: f64) -> Option<Self> {
+        if !difficulty.is_finite() || difficulty <= 0.0 {
+            return None;
+        }
+        if difficulty <= 1.0 {
+            return Some(Target::MAX);
+        }
+
+        // difficulty = m * 2^e with m in [1, 2), so the target is
+        // 2^(256 - e) / m - 1. Take 2^63 / m as a 64-bit mantissa and shift
+        // it into place; 2^63 / 1 keeps powers of two exact. A difficulty
+        // above 1 is a normal f64, so e and m come straight from its bits.
+        let raw = difficulty.to_bits();
+        let e = ((raw >> 52) & 0x7ff) as i32 - 1023;
+        let m = f64::from_bits(raw & 0x000f_ffff_ffff_ffff | 1023 << 52);
+        let q = (9_223_372_036_854_775_808.0 / m) as u64;
+
+        // Place q * 2^shift into the words; bits shifted below 2^0 drop off
+        let shift = 256 - 63 - e;
+        let words = core::array::from_fn(|i| {
+            let r = shift - 32 * (7 - i as i32);
+            match r {
+                0..=31 => (q << r) as u32,
+                -63..=-1 => (q >> -r) as u32,
+                _ => 0,
+            }
+        });
+        Some(Target(words).saturating_sub_one())
+    }
+
+    /// Floating difficulty of this target, the inverse of `from_difficulty`.
+    pub fn difficulty(&self) -> f64 {
+        let mut value = 0.0;
+        for &word in &self.0 {
+            value = value * 4_294_967_296.0 + word as f64;
+        }
+        // (MAX + 1) / (target + 1); the +1s keep powers of two exact
+        TWO_POW_256 / (value + 1.0)
+    }
+
+    /// Leading zero bits every hash meeting this target must have.
+    pub fn leading_zeros(&self) -> u32 {
+        let mut zeros = 0;
+        for &word in &self.0 {
+            zeros += word.leading_zeros();
+            if word != 0 {
+                break;
+            }
+        }
+        zeros
+    }
+
+    /// Does a raw hash state meet the target?
+    #[inline(always)]
+    pub fn is_met_by(&self, state: &[u32; 8]) -> bool {
+        // [u32; 8] compares word by word, i.e. as the big-endian hash
+        *state <= self.0
+    }
+
+    /// Does a hash, as bytes, meet the target?
+    pub fn is_met_by_bytes(&self, hash: &[u8; 32]) -> bool {
+        Target::from_bytes(hash) <= *self
+    }
+
+    /// Largest leading word of a hash that can meet the target. A hash
+    /// whose h[0] is above it fails without looking at the rest.
+    #[inline(always)]
+    pub fn leading_word(&self) -> u32 {
+        self.0[0]
+    }
+
+    /// Parse 64 hex digits, big-endian.
+    pub fn from_hex(hex: &str) -> Result<Self, ParseTargetError> {
+        let hex = hex.as_bytes();
+        if hex.len() != 64 {
+            return Err(ParseTargetError::Length);
+        }
+        let mut bytes = [0u8; 32];
+        for (byte, pair) in bytes.iter_mut().zip(hex.chunks_exact(2)) {
+            let hi = (pair[0] as char).to_digit(16).ok_or(ParseTargetError::InvalidDigit)?;
+            let lo = (pair[1] as char).to_digit(16).ok_or(ParseTargetError::InvalidDigit)?;
+            *byte = (hi << 4 | lo) as u8;
+        }
+        Ok(Target::from_bytes(&bytes))
+    }
+
+    fn saturating_sub_one(mut self) -> Self {
+        for word in self.0.iter_mut().rev() {
+            let (w, borrow) = word.overflowing_sub(1);
+            *word = w;
+            if !borrow {
+                return self;
+            }
+        }
+        Target::ZERO
+    }
+}
+
+impl fmt::Display for Target {
+    /// 64 lowercase hex digits.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for word in &self.0 {
+            write!(f, "{:08x}", word)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::meets_difficulty_bytes;
+
+    #[test]
+    fn test_from_bits_matches_meets_difficulty() {
+        let mut hash = [0xFFu8; 32];
+        for bits in 0..=256u32 {
+            let target = Target::from_bits(bits);
+            assert_eq!(target.leading_zeros(), bits.min(256));
+            // Hashes right at and just past the boundary
+            for zeros in [bits.saturating_sub(1), bits, bits + 1] {
+                hash.fill(0xFF);
+                for bit in 0..zeros.min(256) {
+                    hash[(bit / 8) as usize] &= !(0x80 >> (bit % 8));
+                }
+                assert_eq!(target.is_met_by_bytes(&hash), meets_difficulty_bytes(&hash, bits), "bits {}", bits);
+            }
+        }
+        assert_eq!(Target::from_bits(0), Target::MAX);
+        assert_eq!(Target::from_bits(256), Target::ZERO);
+        assert_eq!(Target::from_legacy_difficulty(6), Target::from_bits(24));
+    }
+
+    #[test]
+    fn test_from_difficulty() {
+        assert_eq!(Target::from_difficulty(1.0), Some(Target::MAX));
+        assert_eq!(Target::from_difficulty(0.5), Some(Target::MAX));
+        for bits in [1u32, 4, 24, 31, 32, 33, 100, 200] {
+            assert_eq!(Target::from_difficulty(2f64.powi(bits as i32)), Some(Target::from_bits(bits)), "bits {}", bits);
+        }
+        for bad in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(Target::from_difficulty(bad), None);
+        }
+        assert_eq!(Target::from_difficulty(1e300), Some(Target::ZERO));
+
+        // Fractional steps sit strictly between the neighbouring bit targets
+        let t24 = Target::from_bits(24);
+        let t25 = Target::from_bits(25);
+        let t = Target::from_difficulty(3.0 * 2f64.powi(23)).unwrap();
+        assert!(t25 < t && t < t24);
+        // 1.5 * 2^24 → target ≈ 2^232 / 1.5 = 0x0000_00aa_aaaa...
+        assert_eq!(t.words()[0], 0x0000_00aa);
+        assert_eq!(t.words()[1], 0xaaaa_aaaa);
+
+        for d in [1.0, 3.0, 1000.0, 123456.789, 2f64.powi(40) * 1.37] {
+            let back = Target::from_difficulty(d).unwrap().difficulty();
+            assert!((back - d).abs() / d < 1e-12, "{} -> {}", d, back);
+        }
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let t = Target::from_difficulty(12345.678).unwrap();
+        let hex = t.to_string();
+        assert_eq!(hex.len(), 64);
+        assert_eq!(Target::from_hex(&hex), Ok(t));
+        assert_eq!(Target::from_hex(&hex.to_uppercase()), Ok(t));
+        assert_eq!(Target::from_bytes(&t.to_bytes()), t);
+        assert_eq!(Target::from_hex("00ff"), Err(ParseTargetError::Length));
+        assert_eq!(Target::from_hex(&"g".repeat(64)), Err(ParseTargetError::InvalidDigit));
+    }
+}
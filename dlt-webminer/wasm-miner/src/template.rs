@@ -0,0 +1,227 @@
+//! Block template — the header fields that go into a block hash.
+//! Ported from dilithiumcoin/blockchain.go CalculateHash():
+//!
+//!   SHA-256(index + timestamp + txData + previousHash + nonce + difficulty)
+//!
+//! where the integers are plain decimal. The bytes before the nonce form the
+//! mining prefix and the difficulty forms the suffix, so the miner's layout
+//! and the verification hash come from the same code. Both follow the
+//! template's `PowScheme`, which is mainnet's unless set otherwise.
+//!
+//! `BlockTemplate` needs `alloc`; `header_hash` checks a header from
+//! borrowed fields without it.
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "alloc")]
+use crate::mining::{MiningJob, RollPolicy, SuffixTooLongError};
+use crate::scheme::{HeaderFields, PowScheme};
+#[cfg(feature = "alloc")]
+use crate::target::Target;
+#[cfg(feature = "alloc")]
+use crate::utils::hash_to_hex;
+
+/// First height whose hash commits to the Merkle root rather than the
+/// transaction JSON (Go's MerkleRootForkHeight).
+pub const MERKLE_ROOT_FORK_HEIGHT: i64 = 6000;
+
+/// Mainnet block hash from its header fields, hashed as they stream in. The
+/// same bytes as `BlockTemplate::finalize`; `tx_data` follows the same fork
+/// rule. `PowScheme::header_hash` covers other schemes.
+pub fn header_hash(
+    index: i64,
+    timestamp: i64,
+    tx_data: &str,
+    previous_hash: &str,
+    nonce: i64,
+    difficulty: i32,
+) -> [u8; 32] {
+    let header = HeaderFields { index, timestamp, tx_data, previous_hash, difficulty, difficulty_bits: 0 };
+    PowScheme::MAINNET.header_hash(&header, nonce)
+}
+
+/// Leading zero bits a header needs: `difficulty_bits`, or 4 per hex digit
+/// of `difficulty` when it's 0, as the Go node does.
+pub fn required_bits(difficulty: i32, difficulty_bits: u32) -> u32 {
+    if difficulty_bits > 0 {
+        difficulty_bits
+    } else {
+        difficulty.max(0) as u32 * 4
+    }
+}
+
+/// Everything in a block hash except the nonce.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockTemplate {
+    pub index: i64,
+    pub timestamp: i64,
+    /// Merkle root hex from `MERKLE_ROOT_FORK_HEIGHT` on; the transaction
+    /// JSON array before it.
+    pub tx_data: String,
+    pub previous_hash: String,
+    /// Legacy difficulty: required leading zero hex digits. This is what
+    /// gets hashed.
+    pub difficulty: i32,
+    /// Required leading zero bits, or 0 to derive them from `difficulty`.
+    pub difficulty_bits: u32,
+    pub scheme: PowScheme,
+}
+
+#[cfg(feature = "alloc")]
+impl BlockTemplate {
+    pub fn new(
+        index: i64,
+        timestamp: i64,
+        tx_data: &str,
+        previous_hash: &str,
+        difficulty: i32,
+        difficulty_bits: u32,
+    ) -> Self {
+        BlockTemplate {
+            index,
+            timestamp,
+            tx_data: String::from(tx_data),
+            previous_hash: String::from(previous_hash),
+            difficulty,
+            difficulty_bits,
+            scheme: PowScheme::MAINNET,
+        }
+    }
+
+    /// The same template under another proof-of-work scheme.
+    pub fn with_scheme(mut self, scheme: PowScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// The header fields, borrowed.
+    pub fn fields(&self) -> HeaderFields<'_> {
+        HeaderFields {
+            index: self.index,
+            timestamp: self.timestamp,
+            tx_data: &self.tx_data,
+            previous_hash: &self.previous_hash,
+            difficulty: self.difficulty,
+            difficulty_bits: self.difficulty_bits,
+        }
+    }
+
+    /// Leading zero bits a solution needs. Falls back to 4 per hex digit of
+    /// `difficulty` when no bit difficulty is set, as the Go node does.
+    pub fn diff_bits(&self) -> u32 {
+        required_bits(self.difficulty, self.difficulty_bits)
+    }
+
+    /// `diff_bits()` as a full target.
+    pub fn target(&self) -> Target {
+        self.scheme.target(&self.fields())
+    }
+
+    /// Append the bytes before the nonce: on mainnet index, timestamp, tx
+    /// data and previous hash.
+    pub fn write_prefix(&self, out: &mut Vec<u8>) {
+        self.scheme.write_fields(&self.fields(), self.scheme.before_nonce(), 0, |b| out.extend_from_slice(b));
+    }
+
+    /// Append the bytes after the nonce: on mainnet the decimal difficulty.
+    pub fn write_suffix(&self, out: &mut Vec<u8>) {
+        self.scheme.write_fields(&self.fields(), self.scheme.after_nonce(), 0, |b| out.extend_from_slice(b));
+    }
+
+    pub fn prefix(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_prefix(&mut out);
+        out
+    }
+
+    pub fn suffix(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_suffix(&mut out);
+        out
+    }
+
+    /// Full hash preimage for `nonce`.
+    pub fn preimage(&self, nonce: i64) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.scheme.write_fields(&self.fields(), self.scheme.fields(), nonce, |b| out.extend_from_slice(b));
+        out
+    }
+
+    /// A mining job for this template at `target()`. It never rolls the
+    /// timestamp; use `MiningJob::from_template` for that. Errs if the
+    /// scheme puts more after the nonce than a job can hash.
+    pub fn mining_job(&self) -> Result<MiningJob, SuffixTooLongError> {
+        MiningJob::from_template(self.clone(), RollPolicy::default())
+    }
+
+    /// Block hash for `nonce`.
+    pub fn finalize(&self, nonce: i64) -> [u8; 32] {
+        self.scheme.header_hash(&self.fields(), nonce)
+    }
+
+    /// Block hash for `nonce` as lowercase hex, as stored in `Block.Hash`.
+    pub fn finalize_hex(&self, nonce: i64) -> String {
+        hash_to_hex(&self.finalize(nonce))
+    }
+
+    /// Is `hash` this template's hash at `nonce`, and does it meet the
+    /// target?
+    pub fn verify(&self, nonce: i64, hash: &[u8; 32]) -> bool {
+        self.scheme.verify(&self.fields(), nonce, hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target::Target;
+
+    #[cfg(feature = "alloc")]
+    fn genesis() -> BlockTemplate {
+        BlockTemplate::new(0, 1738368000, "[]", "0", 6, 0)
+    }
+
+    #[test]
+    fn test_header_hash_without_alloc() {
+        let hash = header_hash(0, 1738368000, "[]", "0", 5892535, 6);
+        assert_eq!(
+            crate::utils::hex_to_hash("0000002835112676fbe3d7588fa08557751aa4045cc8575f16037247350815ae"),
+            Some(hash)
+        );
+        assert!(Target::from_bits(required_bits(6, 0)).is_met_by_bytes(&hash));
+        assert!(!Target::from_bits(required_bits(6, 27)).is_met_by_bytes(&hash));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_genesis_finalize() {
+        assert_eq!(
+            genesis().finalize_hex(5892535),
+            "0000002835112676fbe3d7588fa08557751aa4045cc8575f16037247350815ae"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_layout_matches_calculate_hash() {
+        let t = BlockTemplate::new(6001, 1738368000, "ab12", "00ff", 6, 26);
+        assert_eq!(t.prefix(), b"60011738368000ab1200ff");
+        assert_eq!(t.suffix(), b"6");
+        assert_eq!(t.preimage(42), b"60011738368000ab1200ff426");
+        assert_eq!(t.finalize(42), crate::sha256::sha256(&t.preimage(42)));
+        assert_eq!(t.diff_bits(), 26);
+        assert_eq!(genesis().diff_bits(), 24);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_mining_job_agrees_with_finalize() {
+        let mut t = genesis();
+        t.difficulty_bits = 10;
+        let result = t.mining_job().unwrap().mine(0, 1, 20_000).expect("solution at 10 bits");
+        assert_eq!(result.hash_hex, t.finalize_hex(result.nonce));
+        assert_eq!(result.timestamp, Some(t.timestamp));
+    }
+}
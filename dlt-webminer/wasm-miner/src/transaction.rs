@@ -0,0 +1,213 @@
+//! Transactions, serialized byte for byte as Go's json.Marshal writes them.
+//! Ported from dilithiumcoin's Transaction struct:
+//!
+//!   from, to, amount, fee (omitempty), data (omitempty), timestamp,
+//!   signature, public_key (omitempty)
+//!
+//! Merkle leaves are SHA-256 of these bytes, and pre-fork block hashes take
+//! the JSON array itself, so any drift from Go's output (field order, an
+//! omitted zero, an escape) means a rejected block.
+
+use core::fmt::Write;
+
+use alloc::string::String;
+
+use crate::json::{ParseJsonError, Reader};
+
+/// A transaction as the node sends and expects it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub struct Transaction {
+    pub from: String,
+    pub to: String,
+    pub amount: i64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fee: i64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub data: String,
+    pub timestamp: i64,
+    pub signature: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub public_key: String,
+}
+
+impl Transaction {
+    /// Append the JSON exactly as Go's json.Marshal writes it.
+    pub fn write_json(&self, out: &mut String) {
+        out.push_str("{\"from\":");
+        write_go_string(out, &self.from);
+        out.push_str(",\"to\":");
+        write_go_string(out, &self.to);
+        let _ = write!(out, ",\"amount\":{}", self.amount);
+        if self.fee != 0 {
+            let _ = write!(out, ",\"fee\":{}", self.fee);
+        }
+        if !self.data.is_empty() {
+            out.push_str(",\"data\":");
+            write_go_string(out, &self.data);
+        }
+        let _ = write!(out, ",\"timestamp\":{}", self.timestamp);
+        out.push_str(",\"signature\":");
+        write_go_string(out, &self.signature);
+        if !self.public_key.is_empty() {
+            out.push_str(",\"public_key\":");
+            write_go_string(out, &self.public_key);
+        }
+        out.push('}');
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    /// Parse one transaction as the node sends it, with Go's leniency:
+    /// omitted fields are zero, unknown ones are ignored.
+    pub fn from_json(json: &str) -> Result<Self, ParseJsonError> {
+        let mut r = Reader::new(json);
+        let tx = Self::read_json(&mut r)?;
+        r.end()?;
+        Ok(tx)
+    }
+
+    pub(crate) fn read_json(r: &mut Reader<'_>) -> Result<Self, ParseJsonError> {
+        let mut tx = Transaction::default();
+        r.object(|r, key| {
+            match key.to_ascii_lowercase().as_str() {
+                "from" => tx.from = r.string("from")?,
+                "to" => tx.to = r.string("to")?,
+                "amount" => tx.amount = r.int("amount")?,
+                "fee" => tx.fee = r.int("fee")?,
+                "data" => tx.data = r.string("data")?,
+                "timestamp" => tx.timestamp = r.int("timestamp")?,
+                "signature" => tx.signature = r.string("signature")?,
+                "public_key" => tx.public_key = r.string("public_key")?,
+                _ => r.skip()?,
+            }
+            Ok(())
+        })?;
+        Ok(tx)
+    }
+
+    pub fn is_coinbase(&self) -> bool {
+        self.from == "SYSTEM"
+    }
+}
+
+/// The JSON array of `txs`: the pre-fork `txData` a block hash commits to.
+pub fn transactions_json(txs: &[Transaction]) -> String {
+    let mut out = String::from("[");
+    for (i, tx) in txs.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        tx.write_json(&mut out);
+    }
+    out.push(']');
+    out
+}
+
+/// Append `s` as a JSON string literal the way Go (1.22 on) escapes it:
+/// `"` and `\`, short escapes for `\b \f \n \r \t`, `\u00XX` for other
+/// control characters, and -- Go's HTML-safe default -- `<`, `>`, `&`,
+/// U+2028 and U+2029 as `\uXXXX`. Everything else is written as is.
+pub fn write_go_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\0'..='\u{1f}' | '<' | '>' | '&' | '\u{2028}' | '\u{2029}' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// `write_go_string` into a new string.
+pub fn go_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    write_go_string(&mut out, s);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer() -> Transaction {
+        Transaction {
+            from: "a<b>".into(),
+            to: "c&d".into(),
+            amount: 5,
+            timestamp: 1700000000,
+            signature: "sig\n".into(),
+            ..Transaction::default()
+        }
+    }
+
+    #[test]
+    fn test_field_order_and_omitempty() {
+        assert_eq!(
+            transfer().to_json(),
+            r#"{"from":"a\u003cb\u003e","to":"c\u0026d","amount":5,"timestamp":1700000000,"signature":"sig\n"}"#
+        );
+        let full = Transaction { fee: 10_000, data: "memo".into(), public_key: "pk".into(), ..transfer() };
+        assert_eq!(
+            full.to_json(),
+            r#"{"from":"a\u003cb\u003e","to":"c\u0026d","amount":5,"fee":10000,"data":"memo","timestamp":1700000000,"signature":"sig\n","public_key":"pk"}"#
+        );
+        // A negative fee isn't Go's zero value, so it's written
+        assert!(Transaction { fee: -1, ..transfer() }.to_json().contains(r#""amount":5,"fee":-1,"timestamp""#));
+    }
+
+    #[test]
+    fn test_go_string_escapes() {
+        assert_eq!(
+            go_string("q\"b\\ \u{0}\u{1f}\u{8}\u{c}\r\t é€😀 \u{2028}\u{2029} <>&"),
+            r#""q\"b\\ \u0000\u001f\b\f\r\t é€😀 \u2028\u2029 \u003c\u003e\u0026""#
+        );
+        assert_eq!(go_string(""), r#""""#);
+        // DEL and other non-control ASCII pass through
+        assert_eq!(go_string("\u{7f}/'"), "\"\u{7f}/'\"");
+    }
+
+    #[test]
+    fn test_transactions_json() {
+        assert_eq!(transactions_json(&[]), "[]");
+        let coinbase = Transaction { from: "SYSTEM".into(), ..transfer() };
+        assert!(coinbase.is_coinbase() && !transfer().is_coinbase());
+        let both = format!("[{},{}]", coinbase.to_json(), transfer().to_json());
+        assert_eq!(transactions_json(&[coinbase, transfer()]), both);
+    }
+
+    #[test]
+    fn test_from_json_round_trips() {
+        let full = Transaction { fee: 10_000, data: "m\u{2028}<".into(), public_key: "pk".into(), ..transfer() };
+        for tx in [transfer(), full] {
+            assert_eq!(Transaction::from_json(&tx.to_json()), Ok(tx));
+        }
+        // Go's leniency: any key case, unknown fields, missing fields
+        let loose = r#" {"FROM":"a","Amount":7,"extra":[{"x":1}],"timestamp":9} "#;
+        let tx = Transaction::from_json(loose).unwrap();
+        assert_eq!((tx.from.as_str(), tx.amount, tx.timestamp, tx.to.as_str()), ("a", 7, 9, ""));
+        assert_eq!(Transaction::from_json(r#"{"amount":"7"}"#), Err(ParseJsonError::Field("amount")));
+        assert_eq!(Transaction::from_json(r#"{"from":"a"} x"#), Err(ParseJsonError::Syntax(13)));
+    }
+
+    #[test]
+    #[cfg(feature = "native-miner")]
+    fn test_deserialize_fills_omitted_fields() {
+        let json = r#"{"from":"a","to":"b","amount":7,"timestamp":9,"signature":"s"}"#;
+        let tx: Transaction = serde_json::from_str(json).unwrap();
+        assert_eq!(tx.to_json(), json);
+    }
+}
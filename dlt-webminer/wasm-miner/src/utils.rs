@@ -1,16 +1,53 @@
-/// Utility functions ported from Go miner.
+//! Utility functions ported from Go miner.
+
+use core::fmt;
 
 const HEX_CHARS: &[u8; 16] = b"0123456789abcdef";
 
 /// Convert a raw 32-byte hash to a lowercase hex string.
-pub fn hash_to_hex(hash: &[u8; 32]) -> String {
+#[cfg(feature = "alloc")]
+pub fn hash_to_hex(hash: &[u8; 32]) -> alloc::string::String {
+    let buf = hash_to_hex_bytes(hash);
+    // Safety: all chars are ASCII hex digits
+    unsafe { alloc::string::String::from_utf8_unchecked(buf.to_vec()) }
+}
+
+/// A raw 32-byte hash as 64 lowercase hex digits, without allocating.
+pub fn hash_to_hex_bytes(hash: &[u8; 32]) -> [u8; 64] {
     let mut buf = [0u8; 64];
     for (i, &b) in hash.iter().enumerate() {
         buf[i * 2] = HEX_CHARS[(b >> 4) as usize];
         buf[i * 2 + 1] = HEX_CHARS[(b & 0x0f) as usize];
     }
-    // Safety: all chars are ASCII hex digits
-    unsafe { String::from_utf8_unchecked(buf.to_vec()) }
+    buf
+}
+
+/// Parse 64 hex digits (either case) back into a raw hash.
+pub fn hex_to_hash(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex.as_bytes();
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut hash = [0u8; 32];
+    for (byte, pair) in hash.iter_mut().zip(hex.chunks_exact(2)) {
+        let hi = (pair[0] as char).to_digit(16)?;
+        let lo = (pair[1] as char).to_digit(16)?;
+        *byte = (hi << 4 | lo) as u8;
+    }
+    Some(hash)
+}
+
+/// Formats a raw hash as lowercase hex, for `write!` into any
+/// `core::fmt::Write` without an intermediate `String`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HashHex<'a>(pub &'a [u8; 32]);
+
+impl fmt::Display for HashHex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let buf = hash_to_hex_bytes(self.0);
+        // Safety: all chars are ASCII hex digits
+        f.write_str(unsafe { core::str::from_utf8_unchecked(&buf) })
+    }
 }
 
 /// Write an i64 as decimal ASCII into buf, returns bytes written.
@@ -46,6 +83,113 @@ pub fn write_i64(buf: &mut [u8], n: i64) -> usize {
     offset + pos
 }
 
+/// In-place decimal odometer for nonces.
+///
+/// Holds the decimal ASCII form of the current nonce and steps it by `stride`
+/// by adding the stride's digits column by column, so only the digits touched
+/// by the addition and its carry chain are rewritten. Digits are kept
+/// right-aligned in the buffer: when a carry runs off the top (999 -> 1000)
+/// the number just grows one byte to the left and `digits()` is relaid out
+/// without moving anything.
+///
+/// Output always matches `write_i64` for the current value. Negative values,
+/// non-positive strides and i64 overflow are outside the odometer's domain
+/// and fall back to `write_i64` per step.
+#[derive(Clone)]
+pub struct DecimalCounter {
+    buf: [u8; 20],
+    len: usize,
+    value: i64,
+    stride: i64,
+    /// Stride digits, least significant first.
+    stride_digits: [u8; 20],
+    stride_len: usize,
+}
+
+impl DecimalCounter {
+    /// Start counting at `start`, stepping by `stride`.
+    pub fn new(start: i64, stride: i64) -> Self {
+        let mut counter = DecimalCounter {
+            buf: [0u8; 20],
+            len: 0,
+            value: start,
+            stride,
+            stride_digits: [0u8; 20],
+            stride_len: 0,
+        };
+        if stride > 0 {
+            let mut s = stride;
+            while s > 0 {
+                counter.stride_digits[counter.stride_len] = (s % 10) as u8;
+                s /= 10;
+                counter.stride_len += 1;
+            }
+        }
+        counter.rewrite();
+        counter
+    }
+
+    /// Current nonce value.
+    #[inline(always)]
+    pub fn value(&self) -> i64 {
+        self.value
+    }
+
+    /// Current nonce as decimal ASCII.
+    #[inline(always)]
+    pub fn digits(&self) -> &[u8] {
+        &self.buf[20 - self.len..]
+    }
+
+    /// Number of decimal digits (including any '-' sign).
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Always false; a nonce has at least one digit.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Step to `value + stride`.
+    #[inline(always)]
+    pub fn advance(&mut self) {
+        let next = self.value.checked_add(self.stride);
+        match next {
+            Some(next) if self.value >= 0 && self.stride > 0 => {
+                self.value = next;
+                let mut carry = 0u8;
+                let mut i = 0;
+                while i < self.stride_len || carry != 0 {
+                    let pos = 19 - i;
+                    let digit = if i < self.len { self.buf[pos] - b'0' } else { 0 };
+                    let sum = digit + self.stride_digits[i] + carry;
+                    let (d, c) = if sum >= 10 { (sum - 10, 1) } else { (sum, 0) };
+                    self.buf[pos] = b'0' + d;
+                    carry = c;
+                    i += 1;
+                }
+                if i > self.len {
+                    self.len = i;
+                }
+            }
+            _ => {
+                self.value = self.value.wrapping_add(self.stride);
+                self.rewrite();
+            }
+        }
+    }
+
+    /// Re-render the whole buffer from `value`.
+    fn rewrite(&mut self) {
+        let mut tmp = [0u8; 20];
+        self.len = write_i64(&mut tmp, self.value);
+        self.buf[20 - self.len..].copy_from_slice(&tmp[..self.len]);
+    }
+}
+
 /// Write an i32 as decimal ASCII into buf, returns bytes written.
 /// Used for Difficulty field (Go's strconv.Itoa).
 pub fn write_i32(buf: &mut [u8], n: i32) -> usize {
@@ -60,10 +204,8 @@ pub fn meets_difficulty_bytes(hash: &[u8; 32], bits: u32) -> bool {
         return true;
     }
     let full_bytes = (bits / 8) as usize;
-    for i in 0..full_bytes {
-        if hash[i] != 0 {
-            return false;
-        }
+    if hash[..full_bytes].iter().any(|&b| b != 0) {
+        return false;
     }
     let rem = bits % 8;
     if rem > 0 {
@@ -96,6 +238,48 @@ mod tests {
 
         let n = write_i64(&mut buf, 1738368000);
         assert_eq!(&buf[..n], b"1738368000");
+
+        let n = write_i64(&mut buf, i64::MAX);
+        assert_eq!(&buf[..n], b"9223372036854775807");
+
+        let n = write_i64(&mut buf, i64::MIN);
+        assert_eq!(&buf[..n], b"-9223372036854775808");
+    }
+
+    #[test]
+    fn test_decimal_counter_matches_write_i64() {
+        let mut buf = [0u8; 20];
+        for &(start, stride) in &[
+            (0i64, 1i64), (995, 1), (9_990, 3), (99_999_990, 7), (1, 999),
+            (5, 123_456_789), (-20, 3), (12, -5),
+            (999_999_999_999_999_950, 1), (i64::MAX - 160, 3),
+        ] {
+            let mut counter = DecimalCounter::new(start, stride);
+            let mut expected = start;
+            for _ in 0..50 {
+                let n = write_i64(&mut buf, expected);
+                assert_eq!(counter.value(), expected);
+                assert_eq!(counter.digits(), &buf[..n], "start={} stride={}", start, stride);
+                counter.advance();
+                expected += stride;
+            }
+        }
+    }
+
+    #[test]
+    fn test_decimal_counter_grows_digit_count() {
+        let mut counter = DecimalCounter::new(98, 1);
+        counter.advance();
+        assert_eq!(counter.digits(), b"99");
+        counter.advance();
+        assert_eq!(counter.digits(), b"100");
+        assert_eq!(counter.len(), 3);
+
+        // Carry that runs through every digit and off the top
+        let mut counter = DecimalCounter::new(999_999_999_999_999_999, 1);
+        counter.advance();
+        assert_eq!(counter.digits(), b"1000000000000000000");
+        assert_eq!(counter.value(), 1_000_000_000_000_000_000);
     }
 
     #[test]
@@ -121,6 +305,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "alloc")]
     fn test_hash_to_hex() {
         let hash = [0u8; 32];
         assert_eq!(hash_to_hex(&hash), "0000000000000000000000000000000000000000000000000000000000000000");
@@ -132,4 +317,20 @@ mod tests {
         assert!(hex.starts_with("ab"));
         assert!(hex.ends_with("cd"));
     }
+
+    #[test]
+    fn test_fixed_size_hex() {
+        let mut hash = [0u8; 32];
+        hash[0] = 0xab;
+        hash[31] = 0xcd;
+        let hex = hash_to_hex_bytes(&hash);
+        assert_eq!(&hex[..4], b"ab00");
+        assert_eq!(&hex[60..], b"00cd");
+        assert_eq!(HashHex(&hash).to_string().as_bytes(), &hex[..]);
+
+        assert_eq!(hex_to_hash(core::str::from_utf8(&hex).unwrap()), Some(hash));
+        assert_eq!(hex_to_hash(&"AB".repeat(32)), Some([0xab; 32]));
+        assert_eq!(hex_to_hash("ab"), None);
+        assert_eq!(hex_to_hash(&"zz".repeat(32)), None);
+    }
 }
@@ -0,0 +1,944 @@
+//! JS API: the wasm-bindgen exports over the core modules. Built with the
+//! default `wasm` feature; plain Rust users can turn it off.
+
+use std::cell::RefCell;
+
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Atomics, BigInt, Int32Array, Object, Reflect, Uint8Array};
+
+#[cfg(all(feature = "threads", target_arch = "wasm32"))]
+use crate::pool;
+use crate::{block, consensus, merkle, mining, scheme, selftest, sha256, target, template, transaction, utils};
+
+thread_local! {
+    // Hash stats across every `mine_batch` call in this instance (worker)
+    static SESSION_STATS: RefCell<mining::HashStats> = RefCell::new(mining::HashStats::default());
+}
+
+/// Compute SHA-256 midstate for a prefix byte array.
+/// Returns a JS object: { h: [u32 x 8], len: number, tail: Uint8Array }
+#[wasm_bindgen]
+pub fn compute_midstate(prefix: &[u8]) -> JsValue {
+    let (state, tail) = sha256::compute_midstate(prefix);
+
+    let obj = Object::new();
+    let h_arr = Array::new_with_length(8);
+    for i in 0..8 {
+        h_arr.set(i as u32, JsValue::from(state.h[i]));
+    }
+    Reflect::set(&obj, &"h".into(), &h_arr).unwrap();
+    Reflect::set(&obj, &"len".into(), &JsValue::from(state.len as f64)).unwrap();
+
+    let tail_u8 = Uint8Array::new_with_length(tail.len() as u32);
+    tail_u8.copy_from(&tail);
+    Reflect::set(&obj, &"tail".into(), &tail_u8).unwrap();
+
+    obj.into()
+}
+
+/// Mine a batch of nonces. Returns null if no solution found,
+/// or { nonce: bigint, hash: string } on success.
+///
+/// Parameters match the Go worker's Mine() function. `start_nonce` and
+/// `stride` are BigInts; throws if the batch would leave 0..=i64::MAX or
+/// `suffix` is too long to mine.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn mine_batch(
+    h0: u32, h1: u32, h2: u32, h3: u32,
+    h4: u32, h5: u32, h6: u32, h7: u32,
+    prefix_tail: &[u8],
+    suffix: &[u8],
+    start_nonce: BigInt,
+    stride: BigInt,
+    batch_size: u32,
+    diff_bits: u32,
+    midstate_len: f64,
+) -> Result<JsValue, JsValue> {
+    let h = [h0, h1, h2, h3, h4, h5, h6, h7];
+    let start = bigint_to_i64("start_nonce", start_nonce)?;
+    let s = bigint_to_i64("stride", stride)?;
+    let ml = midstate_len_to_u64(midstate_len)?;
+    mining::check_nonce_range(start, s, batch_size).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    check_suffix(suffix)?;
+
+    let mut job = mining::MiningJob::from_midstate(h, ml, prefix_tail, suffix, diff_bits);
+    let result = job.mine(start, s, batch_size);
+    SESSION_STATS.with(|stats| stats.borrow_mut().merge(job.batch_stats()));
+    Ok(mining_result_to_js(result))
+}
+
+/// Hash stats across all `mine_batch` calls in this WASM instance:
+/// { hashes, histogram, best } as for `MiningJob.session_stats()`.
+#[wasm_bindgen]
+pub fn session_stats() -> JsValue {
+    SESSION_STATS.with(|stats| hash_stats_to_js(&stats.borrow()))
+}
+
+#[wasm_bindgen]
+pub fn reset_session_stats() {
+    SESSION_STATS.with(|stats| *stats.borrow_mut() = mining::HashStats::default());
+}
+
+/// 256-bit proof-of-work target; a hash meets it if it is numerically at
+/// or below it. Finer than leading zero bits, for pool vardiff.
+#[wasm_bindgen]
+pub struct Target {
+    inner: target::Target,
+}
+
+#[wasm_bindgen]
+impl Target {
+    /// Target for `bits` leading zero bits.
+    pub fn from_bits(bits: u32) -> Target {
+        Target { inner: target::Target::from_bits(bits) }
+    }
+
+    /// Target for a pool-style difficulty (`MAX / difficulty`). Throws
+    /// unless the difficulty is a positive finite number.
+    pub fn from_difficulty(difficulty: f64) -> Result<Target, JsValue> {
+        target::Target::from_difficulty(difficulty)
+            .map(|inner| Target { inner })
+            .ok_or_else(|| JsValue::from_str("difficulty must be a positive finite number"))
+    }
+
+    /// Parse 64 hex digits.
+    pub fn from_hex(hex: &str) -> Result<Target, JsValue> {
+        target::Target::from_hex(hex).map(|inner| Target { inner }).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.inner.to_string()
+    }
+
+    pub fn difficulty(&self) -> f64 {
+        self.inner.difficulty()
+    }
+
+    /// Leading zero bits every hash meeting the target has.
+    pub fn leading_zeros(&self) -> u32 {
+        self.inner.leading_zeros()
+    }
+}
+
+/// Reusable mining job. Holds the midstate, tail and suffix in WASM
+/// memory so each batch only passes the nonce range.
+///
+/// ```js
+/// const job = new MiningJob(prefixBytes, suffixBytes, diffBits);
+/// const hit = job.mine(workerId, numWorkers, BATCH_SIZE);
+/// const again = job.mine(job.next_nonce(), numWorkers, BATCH_SIZE);
+/// ```
+#[wasm_bindgen]
+pub struct MiningJob {
+    inner: mining::MiningJob,
+}
+
+#[wasm_bindgen]
+impl MiningJob {
+    /// Build a job from the bytes before the nonce and after it. Throws
+    /// if `suffix` is too long to mine.
+    #[wasm_bindgen(constructor)]
+    pub fn new(prefix: &[u8], suffix: &[u8], diff_bits: u32) -> Result<MiningJob, JsValue> {
+        check_suffix(suffix)?;
+        Ok(MiningJob { inner: mining::MiningJob::new(prefix, suffix, diff_bits) })
+    }
+
+    /// Build a job from a `compute_midstate` result ({ h, len, tail }).
+    pub fn from_midstate(midstate: &JsValue, suffix: &[u8], diff_bits: u32) -> Result<MiningJob, JsValue> {
+        let field = |name: &str| Reflect::get(midstate, &name.into());
+
+        let h_arr: Array = field("h")?.dyn_into()?;
+        if h_arr.length() != 8 {
+            return Err(JsValue::from_str("midstate.h must have 8 words"));
+        }
+        let mut h = [0u32; 8];
+        for (i, word) in h.iter_mut().enumerate() {
+            *word = midstate_word(h_arr.get(i as u32))?;
+        }
+
+        let len = midstate_len_to_u64(field("len")?.as_f64().ok_or("midstate.len must be a number")?)?;
+        let tail = Uint8Array::new(&field("tail")?).to_vec();
+        check_suffix(suffix)?;
+
+        Ok(MiningJob { inner: mining::MiningJob::from_midstate(h, len, &tail, suffix, diff_bits) })
+    }
+
+    /// Try `count` nonces `start, start + stride, ...` (BigInts). Returns
+    /// null if no solution found, or { nonce: bigint, hash: string } on
+    /// success, plus `timestamp` (bigint) for jobs from a `BlockTemplate`.
+    /// Throws if the batch would leave 0..=i64::MAX.
+    pub fn mine(&mut self, start: BigInt, stride: BigInt, count: u32) -> Result<JsValue, JsValue> {
+        let (start, stride) = nonce_range(start, stride, count)?;
+        Ok(mining_result_to_js(self.inner.mine(start, stride, count)))
+    }
+
+    /// `mine` with the current unix time in seconds (BigInt), so a job from
+    /// a `BlockTemplate` can roll its timestamp once it is too old. After a
+    /// roll the batch restarts from the job's first nonce, not `start`.
+    pub fn mine_at(&mut self, start: BigInt, stride: BigInt, count: u32, now_secs: BigInt) -> Result<JsValue, JsValue> {
+        let (start, stride) = nonce_range(start, stride, count)?;
+        let now = bigint_to_i64("now_secs", now_secs)?;
+        Ok(mining_result_to_js(self.inner.mine_at(start, stride, count, Some(now))))
+    }
+
+    /// `mine`, but gives up early once `flag[0]` stops reading `generation`.
+    /// `flag` is an `Int32Array` over a `SharedArrayBuffer` that the main
+    /// thread bumps with `Atomics.store` on a new block; it is checked every
+    /// few thousand nonces, so stale work stops within milliseconds.
+    /// Returns { scanned, cancelled, solution: null | { nonce, hash,
+    /// timestamp? } }; `next_nonce()` is just past the last nonce hashed.
+    pub fn mine_until(
+        &mut self,
+        start: BigInt,
+        stride: BigInt,
+        count: u32,
+        flag: &Int32Array,
+        generation: i32,
+    ) -> Result<JsValue, JsValue> {
+        let (start, stride) = nonce_range(start, stride, count)?;
+        // Throws here, not halfway through, if `flag` is unusable
+        Atomics::load(flag, 0)?;
+        let batch = self.inner.mine_until(start, stride, count, None, || {
+            Atomics::load(flag, 0) != Ok(generation)
+        });
+
+        let obj = Object::new();
+        Reflect::set(&obj, &"scanned".into(), &JsValue::from(batch.scanned)).unwrap();
+        Reflect::set(&obj, &"cancelled".into(), &JsValue::from_bool(batch.cancelled)).unwrap();
+        Reflect::set(&obj, &"solution".into(), &mining_result_to_js(batch.result)).unwrap();
+        Ok(obj.into())
+    }
+
+    /// Mine from `start` (BigInt) for about `duration_ms`, sizing batches
+    /// from the measured hash rate instead of a fixed `BATCH_SIZE`. Returns
+    /// { hashes, last_nonce: bigint | null, solution: null | { nonce, hash,
+    /// timestamp? } }; carry on from `next_nonce()`. The clock is
+    /// `Date.now()`, which also drives age-based timestamp rolling.
+    pub fn mine_for(&mut self, start: BigInt, stride: BigInt, duration_ms: f64) -> Result<JsValue, JsValue> {
+        let (start, stride) = nonce_range(start, stride, 1)?;
+        if !(duration_ms.is_finite() && duration_ms >= 0.0) {
+            return Err(JsValue::from_str("duration_ms must be a non-negative number"));
+        }
+        let now = (js_sys::Date::now() / 1000.0) as i64;
+        let timed = self.inner.mine_for(start, stride, duration_ms, Some(now), js_sys::Date::now);
+
+        let obj = Object::new();
+        Reflect::set(&obj, &"hashes".into(), &JsValue::from(timed.hashes as f64)).unwrap();
+        let last_nonce = timed.last_nonce.map_or(JsValue::NULL, JsValue::from);
+        Reflect::set(&obj, &"last_nonce".into(), &last_nonce).unwrap();
+        Reflect::set(&obj, &"solution".into(), &mining_result_to_js(timed.result)).unwrap();
+        Ok(obj.into())
+    }
+
+    /// Scan all `count` nonces and return every share:
+    /// { shares: [{ nonce, hash, block, timestamp? }], scanned }. `block`
+    /// marks hashes that also meet the `block` target. Stops early once
+    /// `max_shares` are found; carry on from `next_nonce()`.
+    pub fn mine_collect(
+        &mut self,
+        start: BigInt,
+        stride: BigInt,
+        count: u32,
+        share: &Target,
+        block: &Target,
+        max_shares: u32,
+    ) -> Result<JsValue, JsValue> {
+        let (start, stride) = nonce_range(start, stride, count)?;
+        let batch = self.inner.mine_collect(start, stride, count, share.inner, block.inner, max_shares as usize);
+
+        let shares = Array::new();
+        for share in batch.shares {
+            let obj = Object::new();
+            Reflect::set(&obj, &"nonce".into(), &JsValue::from(share.nonce)).unwrap();
+            Reflect::set(&obj, &"hash".into(), &JsValue::from_str(&share.hash_hex)).unwrap();
+            Reflect::set(&obj, &"block".into(), &JsValue::from_bool(share.is_block)).unwrap();
+            if let Some(ts) = share.timestamp {
+                Reflect::set(&obj, &"timestamp".into(), &JsValue::from(ts)).unwrap();
+            }
+            shares.push(&obj);
+        }
+        let obj = Object::new();
+        Reflect::set(&obj, &"shares".into(), &shares).unwrap();
+        Reflect::set(&obj, &"scanned".into(), &JsValue::from(batch.scanned)).unwrap();
+        Ok(obj.into())
+    }
+
+    /// Roll the template timestamp after `max_nonces` (BigInt) nonces on
+    /// it, or once it is `max_age_secs` behind the clock given to
+    /// `mine_at`. 0 disables either limit.
+    pub fn set_roll_policy(&mut self, max_nonces: BigInt, max_age_secs: u32) -> Result<(), JsValue> {
+        let max_nonces = u64::try_from(max_nonces)
+            .map_err(|_| JsValue::from_str("max_nonces must be a BigInt in the u64 range"))?;
+        self.inner.set_roll_policy(mining::RollPolicy { max_nonces, max_age_secs: max_age_secs as i64 });
+        Ok(())
+    }
+
+    /// Mine against `target` instead of whole leading zero bits. Kept until
+    /// the next `reset`.
+    pub fn set_target(&mut self, target: &Target) {
+        self.inner.set_target(target.inner);
+    }
+
+    pub fn target(&self) -> Target {
+        Target { inner: self.inner.target() }
+    }
+
+    /// Current template timestamp, or undefined for jobs without a template.
+    pub fn timestamp(&self) -> Option<i64> {
+        self.inner.template().map(|t| t.timestamp)
+    }
+
+    /// Stats for the last `mine` / `mine_collect` call:
+    /// { hashes, histogram, best }. `histogram[n]` counts hashes with `n`
+    /// leading zero bits (the last entry: 32 or more); `best` is null or
+    /// { nonce, hash, zeros, timestamp? }.
+    pub fn batch_stats(&self) -> JsValue {
+        hash_stats_to_js(self.inner.batch_stats())
+    }
+
+    /// Stats since the job was built or `reset_stats()`; survives `reset`.
+    pub fn session_stats(&self) -> JsValue {
+        hash_stats_to_js(self.inner.session_stats())
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.inner.reset_stats();
+    }
+
+    /// Where the next `mine` call should start to carry on from the last.
+    pub fn next_nonce(&self) -> i64 {
+        self.inner.next_nonce()
+    }
+
+    /// Point the job at new work, reusing its buffers. Throws, leaving the
+    /// job as it was, if `suffix` is too long to mine.
+    pub fn reset(&mut self, prefix: &[u8], suffix: &[u8], diff_bits: u32) -> Result<(), JsValue> {
+        check_suffix(suffix)?;
+        self.inner.reset(prefix, suffix, diff_bits);
+        Ok(())
+    }
+}
+
+/// Mining threads sharing this module's memory, in a `threads` build.
+///
+/// ```js
+/// const pool = new MinerPool(new URL('./pool-worker.js', import.meta.url).href);
+/// pool.start(template.mining_job(), navigator.hardwareConcurrency);
+/// setInterval(() => { const { hashes, solutions } = pool.poll(); ... }, 500);
+/// ```
+#[cfg(all(feature = "threads", target_arch = "wasm32"))]
+#[wasm_bindgen]
+pub struct MinerPool {
+    inner: pool::MinerPool,
+    worker_url: String,
+}
+
+#[cfg(all(feature = "threads", target_arch = "wasm32"))]
+#[wasm_bindgen]
+impl MinerPool {
+    /// `worker_url` is the module worker script that runs a pool thread
+    /// (web/pool-worker.js).
+    #[wasm_bindgen(constructor)]
+    pub fn new(worker_url: &str) -> MinerPool {
+        MinerPool { inner: pool::MinerPool::new(), worker_url: worker_url.to_string() }
+    }
+
+    /// Mine `job` from its `next_nonce()` on `threads` threads and return
+    /// its generation. Call again with new work when the tip changes;
+    /// threads are only respawned if `threads` changes.
+    pub fn start(&mut self, job: &MiningJob, threads: u32) -> Result<u32, JsValue> {
+        let url = &self.worker_url;
+        let mut error = None;
+        let generation = self.inner.start(&job.inner, threads as usize, |thread| {
+            if let Err(e) = spawn_pool_worker(url, thread) {
+                error.get_or_insert(e);
+            }
+        });
+        error.map_or(Ok(generation), Err)
+    }
+
+    /// { generation, hashes, running, solutions: [{ nonce, hash,
+    /// timestamp?, thread, generation }] }, draining the solutions.
+    pub fn poll(&self) -> JsValue {
+        let status = self.inner.poll();
+        let solutions = Array::new();
+        for solution in status.solutions {
+            let obj = mining_result_to_js(Some(solution.result));
+            Reflect::set(&obj, &"thread".into(), &JsValue::from(solution.thread as u32)).unwrap();
+            Reflect::set(&obj, &"generation".into(), &JsValue::from(solution.generation)).unwrap();
+            solutions.push(&obj);
+        }
+        let obj = Object::new();
+        Reflect::set(&obj, &"generation".into(), &JsValue::from(status.generation)).unwrap();
+        Reflect::set(&obj, &"hashes".into(), &JsValue::from(status.hashes as f64)).unwrap();
+        Reflect::set(&obj, &"running".into(), &JsValue::from(status.running as u32)).unwrap();
+        Reflect::set(&obj, &"solutions".into(), &solutions).unwrap();
+        obj.into()
+    }
+
+    /// Stop every thread within a few milliseconds; their workers close.
+    pub fn stop(&mut self) {
+        self.inner.stop();
+    }
+}
+
+/// Start a module worker on `url` and hand it `thread` to run against our
+/// module and memory (see `pool_worker_entry`).
+#[cfg(all(feature = "threads", target_arch = "wasm32"))]
+fn spawn_pool_worker(url: &str, thread: pool::PoolThread) -> Result<(), JsValue> {
+    let options = web_sys::WorkerOptions::new();
+    options.set_type(web_sys::WorkerType::Module);
+    let worker = web_sys::Worker::new_with_options(url, &options)?;
+
+    // Boxed again so a thin pointer fits in a u32
+    let ptr = Box::into_raw(Box::new(thread));
+    let msg = Array::of3(&wasm_bindgen::module(), &wasm_bindgen::memory(), &JsValue::from(ptr as u32));
+    if let Err(e) = worker.post_message(&msg) {
+        // Safety: the worker never got the pointer, so it is still ours
+        drop(unsafe { Box::from_raw(ptr) });
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Entry point for web/pool-worker.js once it has instantiated the module
+/// on the shared memory: runs the pool thread `spawn_pool_worker` sent.
+#[cfg(all(feature = "threads", target_arch = "wasm32"))]
+#[wasm_bindgen]
+pub fn pool_worker_entry(ptr: u32) {
+    // Safety: `ptr` came from `Box::into_raw` in `spawn_pool_worker` and is
+    // posted to exactly one worker
+    let thread = unsafe { Box::from_raw(ptr as *mut pool::PoolThread) };
+    thread();
+}
+
+fn mining_result_to_js(result: Option<mining::MiningResult>) -> JsValue {
+    match result {
+        Some(result) => {
+            let obj = Object::new();
+            Reflect::set(&obj, &"nonce".into(), &JsValue::from(result.nonce)).unwrap();
+            Reflect::set(&obj, &"hash".into(), &JsValue::from_str(&result.hash_hex)).unwrap();
+            if let Some(ts) = result.timestamp {
+                Reflect::set(&obj, &"timestamp".into(), &JsValue::from(ts)).unwrap();
+            }
+            obj.into()
+        }
+        None => JsValue::NULL,
+    }
+}
+
+fn hash_stats_to_js(stats: &mining::HashStats) -> JsValue {
+    let obj = Object::new();
+    Reflect::set(&obj, &"hashes".into(), &JsValue::from(stats.hashes() as f64)).unwrap();
+
+    let histogram = Array::new_with_length(stats.histogram.len() as u32);
+    for (i, &n) in stats.histogram.iter().enumerate() {
+        histogram.set(i as u32, JsValue::from(n as f64));
+    }
+    Reflect::set(&obj, &"histogram".into(), &histogram).unwrap();
+
+    let best = match stats.best() {
+        Some(best) => {
+            let b = Object::new();
+            Reflect::set(&b, &"nonce".into(), &JsValue::from(best.nonce)).unwrap();
+            Reflect::set(&b, &"hash".into(), &JsValue::from_str(&best.hash_hex)).unwrap();
+            Reflect::set(&b, &"zeros".into(), &JsValue::from(best.leading_zeros)).unwrap();
+            if let Some(ts) = best.timestamp {
+                Reflect::set(&b, &"timestamp".into(), &JsValue::from(ts)).unwrap();
+            }
+            b.into()
+        }
+        None => JsValue::NULL,
+    };
+    Reflect::set(&obj, &"best".into(), &best).unwrap();
+    obj.into()
+}
+
+/// Read a BigInt argument as i64. Numbers and BigInts outside the i64
+/// range are rejected rather than truncated.
+fn bigint_to_i64(name: &str, value: BigInt) -> Result<i64, JsValue> {
+    i64::try_from(value).map_err(|_| JsValue::from_str(&format!("{} must be a BigInt in the i64 range", name)))
+}
+
+/// Read and check a `start`/`stride` pair for a batch of `count` nonces.
+fn nonce_range(start: BigInt, stride: BigInt, count: u32) -> Result<(i64, i64), JsValue> {
+    let start = bigint_to_i64("start", start)?;
+    let stride = bigint_to_i64("stride", stride)?;
+    mining::check_nonce_range(start, stride, count).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok((start, stride))
+}
+
+/// A midstate word must be a whole number in the u32 range; anything else
+/// would otherwise truncate or saturate into a different midstate.
+fn midstate_word(value: JsValue) -> Result<u32, JsValue> {
+    match value.as_f64() {
+        Some(n) if n.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&n) => Ok(n as u32),
+        _ => Err(JsValue::from_str("midstate.h words must be integers from 0 to 2^32 - 1")),
+    }
+}
+
+/// Midstate lengths stay JS numbers (they're at most a few KB), but must
+/// be whole blocks.
+fn midstate_len_to_u64(len: f64) -> Result<u64, JsValue> {
+    if !(0.0..=u32::MAX as f64).contains(&len) || len.fract() != 0.0 || len % 64.0 != 0.0 {
+        return Err(JsValue::from_str("midstate length must be a whole number of 64-byte blocks"));
+    }
+    Ok(len as u64)
+}
+
+/// A suffix must fit a mining job; building one from a longer suffix would
+/// panic and take the instance with it.
+fn check_suffix(suffix: &[u8]) -> Result<(), JsValue> {
+    mining::check_suffix(suffix).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// A field of a JS object, with `null` read as missing.
+fn js_field(obj: &JsValue, name: &str) -> Result<JsValue, JsValue> {
+    Reflect::get(obj, &name.into()).map(|v| if v.is_null() { JsValue::UNDEFINED } else { v })
+}
+
+/// A string field of a `kind` object; a missing optional one is empty.
+fn js_string(obj: &JsValue, kind: &str, name: &str, required: bool) -> Result<String, JsValue> {
+    let value = js_field(obj, name)?;
+    match value.as_string() {
+        Some(s) => Ok(s),
+        None if value.is_undefined() && !required => Ok(String::new()),
+        None => Err(JsValue::from_str(&format!("{} {} must be a string", kind, name))),
+    }
+}
+
+/// An integer field of a `kind` object, as a number or BigInt; a missing
+/// optional one is 0.
+fn js_int(obj: &JsValue, kind: &str, name: &str, required: bool) -> Result<i64, JsValue> {
+    let value = js_field(obj, name)?;
+    if let Some(n) = value.as_f64() {
+        // Past 2^53 a number has already lost digits; those need a BigInt
+        if n.fract() == 0.0 && n.abs() <= 9_007_199_254_740_991.0 {
+            return Ok(n as i64);
+        }
+    } else if value.is_bigint() {
+        return bigint_to_i64(name, value.unchecked_into());
+    } else if value.is_undefined() && !required {
+        return Ok(0);
+    }
+    Err(JsValue::from_str(&format!("{} {} must be a safe integer or BigInt", kind, name)))
+}
+
+/// Read a JS transaction object ({ from, to, amount, fee?, data?,
+/// timestamp, signature, public_key? }). Integers may be numbers or
+/// BigInts; missing optional fields are Go's zero values.
+fn js_to_transaction(tx: &JsValue) -> Result<transaction::Transaction, JsValue> {
+    let string = |name: &str, required: bool| js_string(tx, "transaction", name, required);
+    let int = |name: &str, required: bool| js_int(tx, "transaction", name, required);
+    Ok(transaction::Transaction {
+        from: string("from", true)?,
+        to: string("to", true)?,
+        amount: int("amount", true)?,
+        fee: int("fee", false)?,
+        data: string("data", false)?,
+        timestamp: int("timestamp", true)?,
+        signature: string("signature", true)?,
+        public_key: string("public_key", false)?,
+    })
+}
+
+fn js_to_transactions(txs: &Array) -> Result<Vec<transaction::Transaction>, JsValue> {
+    txs.iter().map(|tx| js_to_transaction(&tx)).collect()
+}
+
+/// A transaction's JSON exactly as Go's json.Marshal writes it: field
+/// order, omitempty for fee, data and public_key, and Go's `<`, `>`, `&`
+/// escaping. Throws on a malformed transaction.
+#[wasm_bindgen]
+pub fn transaction_to_json(tx: &JsValue) -> Result<String, JsValue> {
+    Ok(js_to_transaction(tx)?.to_json())
+}
+
+/// The JSON array of `txs`: the txData a pre-fork block hash commits to.
+#[wasm_bindgen]
+pub fn transactions_json(txs: &Array) -> Result<String, JsValue> {
+    Ok(transaction::transactions_json(&js_to_transactions(txs)?))
+}
+
+/// Merkle root (hex) of transaction objects, serialized as Go would.
+#[wasm_bindgen]
+pub fn compute_merkle_root_txs(txs: &Array) -> Result<String, JsValue> {
+    Ok(merkle::transaction_merkle_root(&js_to_transactions(txs)?))
+}
+
+/// Read a JS block object with Go's field names ({ Index, Timestamp,
+/// transactions, MerkleRoot?, PreviousHash, Hash?, Nonce, Difficulty,
+/// DifficultyBits? }), as tx-builder.js's blockToJSON takes it.
+fn js_to_block(block: &JsValue) -> Result<block::Block, JsValue> {
+    let string = |name: &str, required: bool| js_string(block, "block", name, required);
+    let int = |name: &str, required: bool| js_int(block, "block", name, required);
+    let txs = js_field(block, "transactions")?;
+    let transactions = if txs.is_undefined() {
+        Vec::new()
+    } else if Array::is_array(&txs) {
+        js_to_transactions(txs.unchecked_ref())?
+    } else {
+        return Err(JsValue::from_str("block transactions must be an array"));
+    };
+    let difficulty = int("Difficulty", true)?;
+    let difficulty_bits = int("DifficultyBits", false)?;
+    Ok(block::Block {
+        index: int("Index", true)?,
+        timestamp: int("Timestamp", true)?,
+        transactions,
+        merkle_root: string("MerkleRoot", false)?,
+        previous_hash: string("PreviousHash", true)?,
+        hash: string("Hash", false)?,
+        nonce: int("Nonce", true)?,
+        difficulty: difficulty.try_into().map_err(|_| JsValue::from_str("block Difficulty is out of range"))?,
+        difficulty_bits: difficulty_bits
+            .try_into()
+            .map_err(|_| JsValue::from_str("block DifficultyBits is out of range"))?,
+    })
+}
+
+fn parse_block(json: &str) -> Result<block::Block, JsValue> {
+    block::Block::from_json(json).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// A block's JSON exactly as Go's json.Marshal writes it, for
+/// `/block/submit`. Throws on a malformed block.
+#[wasm_bindgen]
+pub fn block_to_json(block: &JsValue) -> Result<String, JsValue> {
+    Ok(js_to_block(block)?.to_json())
+}
+
+/// Re-serialize block JSON from the node (or anywhere) canonically, so
+/// Nonces past 2^53 never pass through a JS number. Throws on bad JSON.
+#[wasm_bindgen]
+pub fn canonical_block_json(json: &str) -> Result<String, JsValue> {
+    Ok(parse_block(json)?.to_json())
+}
+
+/// A block object's hash (hex) as the node computes it: over the Merkle
+/// root from the fork on, the transaction JSON before it.
+#[wasm_bindgen]
+pub fn block_hash(block: &JsValue) -> Result<String, JsValue> {
+    Ok(js_to_block(block)?.calculate_hash())
+}
+
+/// The bytes a block object's hash is SHA-256 of, in the encoding its
+/// height calls for: the transaction JSON below the Merkle fork, the Merkle
+/// root from it on.
+#[wasm_bindgen]
+pub fn block_hash_preimage(block: &JsValue) -> Result<Vec<u8>, JsValue> {
+    Ok(js_to_block(block)?.hash_preimage())
+}
+
+/// `block_hash_preimage` for block JSON.
+#[wasm_bindgen]
+pub fn block_json_hash_preimage(json: &str) -> Result<Vec<u8>, JsValue> {
+    Ok(parse_block(json)?.hash_preimage())
+}
+
+/// `block_hash` for block JSON, e.g. straight from the node's API.
+#[wasm_bindgen]
+pub fn block_json_hash(json: &str) -> Result<String, JsValue> {
+    Ok(parse_block(json)?.calculate_hash())
+}
+
+/// Read consensus params ({ initial_reward?, halving_interval?, min_fee? });
+/// missing fields, or no object at all, are mainnet's.
+fn js_to_params(params: &JsValue) -> Result<consensus::ConsensusParams, JsValue> {
+    let mainnet = consensus::ConsensusParams::MAINNET;
+    if params.is_undefined() || params.is_null() {
+        return Ok(mainnet);
+    }
+    let int = |name: &str, default: i64| -> Result<i64, JsValue> {
+        if js_field(params, name)?.is_undefined() {
+            Ok(default)
+        } else {
+            js_int(params, "params", name, true)
+        }
+    };
+    let halving_interval = int("halving_interval", mainnet.halving_interval)?;
+    if halving_interval <= 0 {
+        return Err(JsValue::from_str("params halving_interval must be positive"));
+    }
+    Ok(consensus::ConsensusParams {
+        initial_reward: int("initial_reward", mainnet.initial_reward)?,
+        halving_interval,
+        min_fee: int("min_fee", mainnet.min_fee)?,
+    })
+}
+
+/// Each broken rule as `{ code, message, ...details }`, e.g.
+/// `{ code: "fee", message, position, fee }`. Integers from i64 are BigInts.
+fn block_errors_to_js(errors: &[consensus::BlockError]) -> Array {
+    use consensus::BlockError;
+    errors
+        .iter()
+        .map(|error| {
+            let obj = Object::new();
+            let set = |key: &str, value: JsValue| Reflect::set(&obj, &key.into(), &value).unwrap();
+            set("code", error.code().into());
+            set("message", error.to_string().into());
+            match error {
+                BlockError::Index { expected } => set("expected", JsValue::from(*expected)),
+                BlockError::PreviousHash { expected }
+                | BlockError::Hash { expected }
+                | BlockError::MerkleRoot { expected } => set("expected", expected.into()),
+                BlockError::Difficulty { required_bits } => set("required_bits", JsValue::from(*required_bits)),
+                BlockError::CoinbaseCount { count } => set("count", JsValue::from(*count as u32)),
+                BlockError::CoinbaseAmount { expected, actual } => {
+                    set("expected", JsValue::from(*expected));
+                    set("actual", JsValue::from(*actual))
+                }
+                BlockError::Fee { position, fee } => {
+                    set("position", JsValue::from(*position as u32));
+                    set("fee", JsValue::from(*fee))
+                }
+            };
+            JsValue::from(obj)
+        })
+        .collect()
+}
+
+fn validate(block: &block::Block, prev_header: &JsValue, params: &JsValue) -> Result<Array, JsValue> {
+    let index = js_int(prev_header, "prev_header", "Index", true)?;
+    let hash = js_string(prev_header, "prev_header", "Hash", true)?;
+    let prev = consensus::PrevHeader { index, hash: &hash };
+    let errors = consensus::validate_block(block, &prev, &js_to_params(params)?).err().unwrap_or_default();
+    Ok(block_errors_to_js(&errors))
+}
+
+/// Check a block object against consensus rules on top of `prev_header`
+/// ({ Index, Hash }): index and hash linkage, the recomputed hash and its
+/// difficulty, the Merkle root, one coinbase paying reward plus fees, and
+/// the minimum fee. `params` may be omitted for mainnet. Returns every
+/// broken rule (see `block_errors_to_js`); an empty array means valid.
+/// Throws only on a malformed argument.
+#[wasm_bindgen]
+pub fn validate_block(block: &JsValue, prev_header: &JsValue, params: &JsValue) -> Result<Array, JsValue> {
+    validate(&js_to_block(block)?, prev_header, params)
+}
+
+/// `validate_block` for block JSON, e.g. straight from the node's API.
+#[wasm_bindgen]
+pub fn validate_block_json(json: &str, prev_header: &JsValue, params: &JsValue) -> Result<Array, JsValue> {
+    validate(&parse_block(json)?, prev_header, params)
+}
+
+/// Compute the Merkle root of transaction JSON strings.
+/// Input: newline-separated JSON strings (one per transaction).
+/// Returns: lowercase hex string of the Merkle root.
+#[wasm_bindgen]
+pub fn compute_merkle_root(txs_newline_separated: &str) -> String {
+    if txs_newline_separated.is_empty() {
+        let h = sha256::sha256(b"");
+        return utils::hash_to_hex(&h);
+    }
+    let txs: Vec<String> = txs_newline_separated
+        .split('\n')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect();
+    merkle::compute_merkle_root(&txs)
+}
+
+/// Check if a hex hash meets the required difficulty (leading zero bits).
+#[wasm_bindgen]
+pub fn check_difficulty(hash_hex: &str, diff_bits: u32) -> bool {
+    if hash_hex.len() != 64 {
+        return false;
+    }
+    let mut hash = [0u8; 32];
+    for (byte, pair) in hash.iter_mut().zip(hash_hex.as_bytes().chunks_exact(2)) {
+        let hi = hex_digit(pair[0]);
+        let lo = hex_digit(pair[1]);
+        if hi == 0xFF || lo == 0xFF {
+            return false;
+        }
+        *byte = (hi << 4) | lo;
+    }
+    utils::meets_difficulty_bytes(&hash, diff_bits)
+}
+
+/// Check if a hex hash is at or below `target`.
+#[wasm_bindgen]
+pub fn check_target(hash_hex: &str, target: &Target) -> bool {
+    target::Target::from_hex(hash_hex).is_ok_and(|hash| hash <= target.inner)
+}
+
+fn hex_digit(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        b'A'..=b'F' => c - b'A' + 10,
+        _ => 0xFF,
+    }
+}
+
+/// Compute the full block hash from components.
+/// Matches Go's CalculateHash():
+///   SHA-256(index_str + timestamp_str + merkle_root + previous_hash + nonce_str + difficulty_str)
+///
+/// Below `MERKLE_ROOT_FORK_HEIGHT` pass the transaction JSON as
+/// `merkle_root`, or use `block_hash`, which picks by height.
+///
+/// `index`, `timestamp` and `nonce` are BigInts (Go int64).
+#[wasm_bindgen]
+pub fn hash_block(
+    index: BigInt,
+    timestamp: BigInt,
+    merkle_root: &str,
+    previous_hash: &str,
+    nonce: BigInt,
+    difficulty: i32,
+) -> Result<String, JsValue> {
+    let index = bigint_to_i64("index", index)?;
+    let timestamp = bigint_to_i64("timestamp", timestamp)?;
+    let nonce = bigint_to_i64("nonce", nonce)?;
+    Ok(template::BlockTemplate::new(index, timestamp, merkle_root, previous_hash, difficulty, 0).finalize_hex(nonce))
+}
+
+/// Block header fields minus the nonce. Owns the CalculateHash() layout, so
+/// the job it mines and the hash it verifies can't disagree.
+///
+/// `tx_data` is the Merkle root, or the transaction JSON before the Merkle
+/// fork. `difficulty_bits` of 0 means 4 bits per hex digit of `difficulty`.
+#[wasm_bindgen]
+pub struct BlockTemplate {
+    inner: template::BlockTemplate,
+}
+
+#[wasm_bindgen]
+impl BlockTemplate {
+    /// `index` and `timestamp` are BigInts.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        index: BigInt,
+        timestamp: BigInt,
+        tx_data: &str,
+        previous_hash: &str,
+        difficulty: i32,
+        difficulty_bits: u32,
+    ) -> Result<BlockTemplate, JsValue> {
+        let index = bigint_to_i64("index", index)?;
+        let timestamp = bigint_to_i64("timestamp", timestamp)?;
+        Ok(BlockTemplate {
+            inner: template::BlockTemplate::new(index, timestamp, tx_data, previous_hash, difficulty, difficulty_bits),
+        })
+    }
+
+    /// Leading zero bits a solution needs.
+    pub fn diff_bits(&self) -> u32 {
+        self.inner.diff_bits()
+    }
+
+    pub fn target(&self) -> Target {
+        Target { inner: self.inner.target() }
+    }
+
+    /// A ready-to-mine job for this template. Throws if its scheme puts
+    /// more after the nonce than a job can hash.
+    pub fn mining_job(&self) -> Result<MiningJob, JsValue> {
+        let inner = self.inner.mining_job().map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(MiningJob { inner })
+    }
+
+    /// Block hash (hex) for `nonce` (BigInt).
+    pub fn finalize(&self, nonce: BigInt) -> Result<String, JsValue> {
+        Ok(self.inner.finalize_hex(bigint_to_i64("nonce", nonce)?))
+    }
+
+    /// Hash and mine under `scheme` instead of mainnet's. Jobs already
+    /// made keep the old one.
+    pub fn set_scheme(&mut self, scheme: &PowScheme) {
+        self.inner.scheme = scheme.inner;
+    }
+
+    pub fn scheme(&self) -> PowScheme {
+        PowScheme { inner: self.inner.scheme }
+    }
+
+    /// Is `hash_hex` the block hash for `nonce` (BigInt), and does it meet
+    /// the target?
+    pub fn verify(&self, nonce: BigInt, hash_hex: &str) -> Result<bool, JsValue> {
+        let nonce = bigint_to_i64("nonce", nonce)?;
+        Ok(utils::hex_to_hash(hash_hex).is_some_and(|hash| self.inner.verify(nonce, &hash)))
+    }
+}
+
+/// How a block hash is computed: hash function, header field order and
+/// hashed difficulty. Mainnet's unless a testnet says otherwise.
+///
+/// ```js
+/// const scheme = new PowScheme("sha256d:index,timestamp,previous_hash,tx_data,nonce,difficulty:bits");
+/// template.set_scheme(scheme);
+/// ```
+#[wasm_bindgen]
+pub struct PowScheme {
+    inner: scheme::PowScheme,
+}
+
+#[wasm_bindgen]
+impl PowScheme {
+    /// Parse `hash:fields:difficulty`. Throws on a malformed scheme.
+    #[wasm_bindgen(constructor)]
+    pub fn new(spec: &str) -> Result<PowScheme, JsValue> {
+        spec.parse::<scheme::PowScheme>()
+            .map(|inner| PowScheme { inner })
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn mainnet() -> PowScheme {
+        PowScheme { inner: scheme::PowScheme::MAINNET }
+    }
+
+    /// The `hash:fields:difficulty` form.
+    pub fn spec(&self) -> String {
+        self.inner.to_string()
+    }
+}
+
+/// Full SHA-256 hash of arbitrary bytes, returned as hex string.
+/// Useful for hashing transaction JSON for Merkle leaves.
+#[wasm_bindgen]
+pub fn sha256_hex(data: &[u8]) -> String {
+    let hash = sha256::sha256(data);
+    utils::hash_to_hex(&hash)
+}
+
+/// Run the known-answer vectors (SHA-256, genesis hash, midstate, and the
+/// genesis nonce through every backend's mining loop). Returns { ok,
+/// error: string | null }; don't mine when `ok` is false, as the engine
+/// would never find a valid block.
+#[wasm_bindgen]
+pub fn self_test() -> JsValue {
+    let result = selftest::self_test();
+    let obj = Object::new();
+    Reflect::set(&obj, &"ok".into(), &JsValue::from(result.is_ok())).unwrap();
+    let error = result.err().map_or(JsValue::NULL, |e| JsValue::from_str(&e.to_string()));
+    Reflect::set(&obj, &"error".into(), &error).unwrap();
+    obj.into()
+}
+
+/// Mine a typical block for about `duration_ms` on this thread through the
+/// real mining path. Returns { hashes, elapsed_ms, hashrate, backend },
+/// where `hashrate` is hashes/s. Timed with `performance.now()`, so call it
+/// from a worker or window; for a pool, run it in each worker and add up
+/// the hashrates.
+#[wasm_bindgen]
+pub fn benchmark(duration_ms: f64) -> Result<JsValue, JsValue> {
+    if !(duration_ms.is_finite() && duration_ms >= 0.0) {
+        return Err(JsValue::from_str("duration_ms must be a non-negative number"));
+    }
+    let performance: web_sys::Performance = Reflect::get(&js_sys::global(), &"performance".into())?
+        .dyn_into()
+        .map_err(|_| JsValue::from_str("benchmark needs performance.now()"))?;
+    let bench = selftest::benchmark(duration_ms, || performance.now());
+
+    let obj = Object::new();
+    Reflect::set(&obj, &"hashes".into(), &JsValue::from(bench.hashes as f64)).unwrap();
+    Reflect::set(&obj, &"elapsed_ms".into(), &JsValue::from(bench.elapsed_ms)).unwrap();
+    Reflect::set(&obj, &"hashrate".into(), &JsValue::from(bench.hashes_per_sec())).unwrap();
+    Reflect::set(&obj, &"backend".into(), &JsValue::from_str(bench.backend.name())).unwrap();
+    Ok(obj.into())
+}
@@ -0,0 +1,369 @@
+//! x86_64 SHA-256 kernels: SHA-NI for single streams and 8-lane AVX2.
+//!
+//! SHA-NI runs the compression function in hardware, one message at a
+//! time, so it suits midstates and per-nonce hashing alike. AVX2 has no SHA
+//! instructions; like `simd`, it interleaves eight same-length messages
+//! across the u32 lanes of a `__m256i` and runs the scalar rounds on all of
+//! them at once, reusing a `HashJob`'s precomputation.
+//!
+//! Every function here needs its CPU feature. The `hasher::ShaNi` and
+//! `hasher::Avx2` backends only exist once runtime detection has found it,
+//! which is what makes calling these sound.
+
+use core::arch::x86_64::*;
+
+use crate::sha256::{rounds_unrolled, HashJob, HASH_JOB_NONCE_BYTES, K256};
+
+/// Compress whole 64-byte `blocks` into `h` with the SHA extensions.
+///
+/// # Safety
+///
+/// The CPU must support `sha`, `ssse3` and `sse4.1`.
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+pub unsafe fn compress_sha_ni(h: &mut [u32; 8], blocks: &[u8]) {
+    debug_assert_eq!(blocks.len() % 64, 0);
+    let bswap = _mm_set_epi64x(0x0c0d_0e0f_0809_0a0b, 0x0405_0607_0001_0203);
+
+    // The SHA instructions keep the state as ABEF and CDGH
+    let abcd = _mm_loadu_si128(h.as_ptr() as *const __m128i);
+    let efgh = _mm_loadu_si128(h.as_ptr().add(4) as *const __m128i);
+    let cdab = _mm_shuffle_epi32::<0xB1>(abcd);
+    let efgh = _mm_shuffle_epi32::<0x1B>(efgh);
+    let mut abef = _mm_alignr_epi8::<8>(cdab, efgh);
+    let mut cdgh = _mm_blend_epi16::<0xF0>(efgh, cdab);
+
+    for block in blocks.chunks_exact(64) {
+        let (abef_in, cdgh_in) = (abef, cdgh);
+        let ptr = block.as_ptr() as *const __m128i;
+        let mut w = [
+            _mm_shuffle_epi8(_mm_loadu_si128(ptr), bswap),
+            _mm_shuffle_epi8(_mm_loadu_si128(ptr.add(1)), bswap),
+            _mm_shuffle_epi8(_mm_loadu_si128(ptr.add(2)), bswap),
+            _mm_shuffle_epi8(_mm_loadu_si128(ptr.add(3)), bswap),
+        ];
+
+        // Four rounds per step; w[i % 4] holds schedule words 4i..4i+4
+        for i in 0..16 {
+            let k = _mm_loadu_si128(K256.as_ptr().add(4 * i) as *const __m128i);
+            let wk = _mm_add_epi32(w[i % 4], k);
+            cdgh = _mm_sha256rnds2_epu32(cdgh, abef, wk);
+            abef = _mm_sha256rnds2_epu32(abef, cdgh, _mm_shuffle_epi32::<0x0E>(wk));
+
+            if i < 12 {
+                // Words 4i+16.. from 4i.. (σ0 part), 4i+9.. and 4i+12.. (σ1)
+                let next = _mm_sha256msg1_epu32(w[i % 4], w[(i + 1) % 4]);
+                let next = _mm_add_epi32(next, _mm_alignr_epi8::<4>(w[(i + 3) % 4], w[(i + 2) % 4]));
+                w[i % 4] = _mm_sha256msg2_epu32(next, w[(i + 3) % 4]);
+            }
+        }
+
+        abef = _mm_add_epi32(abef, abef_in);
+        cdgh = _mm_add_epi32(cdgh, cdgh_in);
+    }
+
+    let feba = _mm_shuffle_epi32::<0x1B>(abef);
+    let dchg = _mm_shuffle_epi32::<0xB1>(cdgh);
+    _mm_storeu_si128(h.as_mut_ptr() as *mut __m128i, _mm_blend_epi16::<0xF0>(feba, dchg));
+    _mm_storeu_si128(h.as_mut_ptr().add(4) as *mut __m128i, _mm_alignr_epi8::<8>(dchg, feba));
+}
+
+/// `HashJob::hash` on SHA-NI: the nonce blocks from a scratch copy, then
+/// the job's fixed trailing blocks.
+///
+/// # Safety
+///
+/// As for `compress_sha_ni`.
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+pub unsafe fn hash_job_sha_ni(job: &HashJob, nonce: &[u8]) -> [u32; 8] {
+    debug_assert_eq!(nonce.len(), job.nonce_len);
+    let nonce_bytes = job.nonce_blocks * 64;
+    let mut block = [0u8; HASH_JOB_NONCE_BYTES];
+    block[..nonce_bytes].copy_from_slice(&job.buf[..nonce_bytes]);
+    block[job.nonce_off..job.nonce_off + job.nonce_len].copy_from_slice(nonce);
+
+    let mut h = job.mid_h;
+    compress_sha_ni(&mut h, &block[..nonce_bytes]);
+    compress_sha_ni(&mut h, &job.buf[nonce_bytes..job.blocks * 64]);
+    h
+}
+
+macro_rules! rotr_x8 {
+    ($x:expr, $n:literal) => {
+        _mm256_or_si256(_mm256_srli_epi32::<$n>($x), _mm256_slli_epi32::<{ 32 - $n }>($x))
+    };
+}
+
+#[target_feature(enable = "avx2")]
+#[inline]
+fn add(a: __m256i, b: __m256i) -> __m256i {
+    _mm256_add_epi32(a, b)
+}
+
+#[target_feature(enable = "avx2")]
+#[inline]
+fn splat(x: u32) -> __m256i {
+    _mm256_set1_epi32(x as i32)
+}
+
+#[target_feature(enable = "avx2")]
+#[inline]
+fn sigma0(x: __m256i) -> __m256i {
+    _mm256_xor_si256(_mm256_xor_si256(rotr_x8!(x, 7), rotr_x8!(x, 18)), _mm256_srli_epi32::<3>(x))
+}
+
+#[target_feature(enable = "avx2")]
+#[inline]
+fn sigma1(x: __m256i) -> __m256i {
+    _mm256_xor_si256(_mm256_xor_si256(rotr_x8!(x, 17), rotr_x8!(x, 19)), _mm256_srli_epi32::<10>(x))
+}
+
+/// Load big-endian word `i` from each of the eight lane blocks.
+#[target_feature(enable = "avx2")]
+#[inline]
+fn load_be32_x8(blocks: &[&[u8]; 8], i: usize) -> __m256i {
+    let off = i * 4;
+    let word = |b: &[u8]| u32::from_be_bytes([b[off], b[off + 1], b[off + 2], b[off + 3]]) as i32;
+    _mm256_setr_epi32(
+        word(blocks[0]), word(blocks[1]), word(blocks[2]), word(blocks[3]),
+        word(blocks[4]), word(blocks[5]), word(blocks[6]), word(blocks[7]),
+    )
+}
+
+/// One compression round on eight lanes. Same register rotation scheme as
+/// the scalar `sha256_round!`.
+macro_rules! sha256_round_x8 {
+    ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr, $f:expr, $g:expr, $h:expr, $k:expr, $w:expr) => {{
+        let s1 = _mm256_xor_si256(_mm256_xor_si256(rotr_x8!($e, 6), rotr_x8!($e, 11)), rotr_x8!($e, 25));
+        let ch = _mm256_xor_si256(_mm256_and_si256($e, $f), _mm256_andnot_si256($e, $g));
+        let temp1 = add(add(add($h, s1), ch), add(splat($k), $w));
+        let s0 = _mm256_xor_si256(_mm256_xor_si256(rotr_x8!($a, 2), rotr_x8!($a, 13)), rotr_x8!($a, 22));
+        let maj = _mm256_or_si256(_mm256_and_si256($a, $b), _mm256_and_si256($c, _mm256_or_si256($a, $b)));
+        let temp2 = add(s0, maj);
+        $d = add($d, temp1);
+        $h = add(temp1, temp2);
+    }};
+}
+
+/// Rounds `START..END` over a full 8-lane schedule, with the working state
+/// in role order. Mirrors `sha256::rounds_from`.
+#[target_feature(enable = "avx2")]
+#[inline]
+fn rounds_from_x8<const START: usize, const END: usize>(s: [__m256i; 8], w: &[__m256i; 64]) -> [__m256i; 8] {
+    let mut v = s;
+    v.rotate_left(START % 8);
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hv] = v;
+    rounds_unrolled!(sha256_round_x8, START, END, K256, w, a, b, c, d, e, f, g, hv);
+    let mut out = [a, b, c, d, e, f, g, hv];
+    out.rotate_right(END % 8);
+    out
+}
+
+/// Block 0 of a job: rounds `start..63` (`start < 16`) with the matching
+/// instantiation.
+#[target_feature(enable = "avx2")]
+#[inline]
+fn rounds_dispatch_x8(s: [__m256i; 8], w: &[__m256i; 64], start: usize) -> [__m256i; 8] {
+    match start {
+        0 => rounds_from_x8::<0, 63>(s, w),
+        1 => rounds_from_x8::<1, 63>(s, w),
+        2 => rounds_from_x8::<2, 63>(s, w),
+        3 => rounds_from_x8::<3, 63>(s, w),
+        4 => rounds_from_x8::<4, 63>(s, w),
+        5 => rounds_from_x8::<5, 63>(s, w),
+        6 => rounds_from_x8::<6, 63>(s, w),
+        7 => rounds_from_x8::<7, 63>(s, w),
+        8 => rounds_from_x8::<8, 63>(s, w),
+        9 => rounds_from_x8::<9, 63>(s, w),
+        10 => rounds_from_x8::<10, 63>(s, w),
+        11 => rounds_from_x8::<11, 63>(s, w),
+        12 => rounds_from_x8::<12, 63>(s, w),
+        13 => rounds_from_x8::<13, 63>(s, w),
+        14 => rounds_from_x8::<14, 63>(s, w),
+        _ => rounds_from_x8::<15, 63>(s, w),
+    }
+}
+
+/// The `a` produced by one round -- all the leading hash word needs from
+/// the final round.
+#[target_feature(enable = "avx2")]
+#[inline]
+fn round_a_x8(s: &[__m256i; 8], k: u32, w: __m256i) -> __m256i {
+    let [a, b, c, _, e, f, g, hv] = *s;
+    let s1 = _mm256_xor_si256(_mm256_xor_si256(rotr_x8!(e, 6), rotr_x8!(e, 11)), rotr_x8!(e, 25));
+    let ch = _mm256_xor_si256(_mm256_and_si256(e, f), _mm256_andnot_si256(e, g));
+    let temp1 = add(add(add(hv, s1), ch), add(splat(k), w));
+    let s0 = _mm256_xor_si256(_mm256_xor_si256(rotr_x8!(a, 2), rotr_x8!(a, 13)), rotr_x8!(a, 22));
+    let maj = _mm256_or_si256(_mm256_and_si256(a, b), _mm256_and_si256(c, _mm256_or_si256(a, b)));
+    add(temp1, add(s0, maj))
+}
+
+/// Run the final round on an 8-lane working state and feed it forward.
+#[target_feature(enable = "avx2")]
+#[inline]
+fn finish_last_round_x8(h: &mut [__m256i; 8], s: [__m256i; 8], w63: __m256i) {
+    let [a, b, c, mut d, e, f, g, mut hv] = s;
+    sha256_round_x8!(a, b, c, d, e, f, g, hv, K256[63], w63);
+    // The macro leaves the new `a` in `hv` and the new `e` in `d`
+    for (hi, si) in h.iter_mut().zip([hv, a, b, c, d, e, f, g]) {
+        *hi = add(*hi, si);
+    }
+}
+
+/// Expand the 8-lane schedule from `w[..from]`.
+#[target_feature(enable = "avx2")]
+#[inline]
+fn expand_schedule_x8(w: &mut [__m256i; 64], from: usize) {
+    for i in from..64 {
+        w[i] = add(add(sigma1(w[i - 2]), w[i - 7]), add(sigma0(w[i - 15]), w[i - 16]));
+    }
+}
+
+#[target_feature(enable = "avx2")]
+#[inline]
+fn splat_state(h: &[u32; 8]) -> [__m256i; 8] {
+    h.map(|x| splat(x))
+}
+
+/// 8-lane `HashJob::run_to_last_round`: everything up to round 63 of the
+/// last block. Returns the chaining value into the last block, the working
+/// state before its final round, and its `w[63]`.
+#[target_feature(enable = "avx2")]
+#[inline]
+fn run_job_to_last_round_x8(job: &HashJob, nonces: [&[u8]; 8]) -> ([__m256i; 8], [__m256i; 8], __m256i) {
+    debug_assert!(nonces.iter().all(|n| n.len() == job.nonce_len));
+
+    // Per-lane copies of the blocks that carry nonce bytes
+    let nonce_bytes = job.nonce_blocks * 64;
+    let mut blocks = [[0u8; HASH_JOB_NONCE_BYTES]; 8];
+    for (block, nonce) in blocks.iter_mut().zip(nonces.iter()) {
+        block[..nonce_bytes].copy_from_slice(&job.buf[..nonce_bytes]);
+        block[job.nonce_off..job.nonce_off + job.nonce_len].copy_from_slice(nonce);
+    }
+    let lanes: [&[u8]; 8] = core::array::from_fn(|lane| &blocks[lane][..]);
+
+    // Block 0: splat the fixed words and partial sums, load the rest per lane
+    let mut w = [_mm256_setzero_si256(); 64];
+    for (i, word) in w.iter_mut().enumerate().take(job.first_word) {
+        *word = splat(job.w0[i]);
+    }
+    for (i, word) in w.iter_mut().enumerate().take(16).skip(job.first_word) {
+        *word = load_be32_x8(&lanes, i);
+    }
+    let split = (job.first_word + 15).max(16);
+    for i in 16..split {
+        w[i] = add(add(sigma1(w[i - 2]), w[i - 7]), splat(job.w0[i]));
+    }
+    expand_schedule_x8(&mut w, split);
+
+    let mut h = splat_state(&job.mid_h);
+    let mut s = rounds_dispatch_x8(splat_state(&job.state0), &w, job.first_word);
+    let mut w63 = w[63];
+
+    for b in 1..job.blocks {
+        finish_last_round_x8(&mut h, s, w63);
+        if b < job.nonce_blocks {
+            let lane_blocks: [&[u8]; 8] = core::array::from_fn(|lane| &lanes[lane][b * 64..]);
+            for (i, word) in w.iter_mut().enumerate().take(16) {
+                *word = load_be32_x8(&lane_blocks, i);
+            }
+            expand_schedule_x8(&mut w, 16);
+        } else {
+            for (wv, &word) in w.iter_mut().zip(job.w_fixed[b].iter()) {
+                *wv = splat(word);
+            }
+        }
+        s = rounds_from_x8::<0, 63>(h, &w);
+        w63 = w[63];
+    }
+
+    (h, s, w63)
+}
+
+#[target_feature(enable = "avx2")]
+#[inline]
+fn lanes_of(v: __m256i) -> [u32; 8] {
+    let mut out = [0u32; 8];
+    // Safety: `out` is 32 writable bytes; storeu has no alignment needs
+    unsafe { _mm256_storeu_si256(out.as_mut_ptr() as *mut __m256i, v) };
+    out
+}
+
+/// `HashJob::leading_word` for eight nonces at once.
+///
+/// # Safety
+///
+/// The CPU must support `avx2`.
+#[target_feature(enable = "avx2")]
+pub unsafe fn leading_words_x8(job: &HashJob, nonces: [&[u8]; 8]) -> [u32; 8] {
+    let (h, s, w63) = run_job_to_last_round_x8(job, nonces);
+    lanes_of(add(h[0], round_a_x8(&s, K256[63], w63)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sha256::{sha256, sha256_block, split_midstate, INIT_H};
+
+    fn has_sha_ni() -> bool {
+        is_x86_feature_detected!("sha") && is_x86_feature_detected!("sse4.1")
+    }
+
+    #[test]
+    fn test_sha_ni_matches_scalar_compression() {
+        if !has_sha_ni() {
+            return;
+        }
+        let data: Vec<u8> = (0..64 * 5u32).map(|i| (i * 131 % 256) as u8).collect();
+        for blocks in 0..=5 {
+            let mut scalar = INIT_H;
+            for block in data[..blocks * 64].chunks(64) {
+                sha256_block(&mut scalar, block);
+            }
+            let mut ni = INIT_H;
+            unsafe { compress_sha_ni(&mut ni, &data[..blocks * 64]) };
+            assert_eq!(ni, scalar, "{} blocks", blocks);
+        }
+    }
+
+    #[test]
+    fn test_hash_jobs_match_scalar() {
+        let suffix = b"6";
+        let sha_ni = has_sha_ni();
+        let avx2 = is_x86_feature_detected!("avx2");
+        for tail_len in [0usize, 3, 17, 40, 55, 63] {
+            let tail: Vec<u8> = (0..tail_len).map(|i| b'a' + (i % 26) as u8).collect();
+            for nonce_len in [1usize, 7, 12, 19] {
+                let job = HashJob::new(INIT_H, 128, &tail, nonce_len, suffix);
+                let mut nonces = [[0u8; 20]; 8];
+                for (lane, n) in nonces.iter_mut().enumerate() {
+                    for (j, d) in n.iter_mut().enumerate().take(nonce_len) {
+                        *d = b'0' + ((j * 3 + lane) % 10) as u8;
+                    }
+                }
+                let lanes: [&[u8]; 8] = core::array::from_fn(|lane| &nonces[lane][..nonce_len]);
+                let expected = lanes.map(|nonce| job.hash(nonce));
+
+                if avx2 {
+                    let lead = unsafe { leading_words_x8(&job, lanes) };
+                    assert_eq!(lead, expected.map(|h| h[0]), "tail {} nonce {}", tail_len, nonce_len);
+                }
+                if sha_ni {
+                    for (nonce, h) in lanes.iter().zip(expected) {
+                        assert_eq!(unsafe { hash_job_sha_ni(&job, nonce) }, h);
+                    }
+                }
+            }
+        }
+
+        // And whole messages, through the midstate
+        if sha_ni {
+            let message = b"6001173836800012ab34cd56ef7890ab12cd34ef567890abcdef0123456789abcdef00000abc";
+            let (state, tail) = split_midstate(message);
+            let mut h = INIT_H;
+            unsafe { compress_sha_ni(&mut h, &message[..64]) };
+            assert_eq!(h, state.h);
+            let job = HashJob::new(h, 64, tail, 0, b"");
+            assert_eq!(crate::sha256::hash_to_bytes(&unsafe { hash_job_sha_ni(&job, b"") }), sha256(message));
+        }
+    }
+}
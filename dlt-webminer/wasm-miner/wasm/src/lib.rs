@@ -0,0 +1,4 @@
+//! The browser miner module: `dlt-webminer` with its wasm-bindgen exports,
+//! linked as a cdylib. `scripts/build.sh` builds this with wasm-pack.
+
+pub use dlt_webminer::*;
@@ -1,5 +1,883 @@
 /* @ts-self-types="./dlt_webminer.d.ts" */
 
+/**
+ * Block header fields minus the nonce. Owns the CalculateHash() layout, so
+ * the job it mines and the hash it verifies can't disagree.
+ *
+ * `tx_data` is the Merkle root, or the transaction JSON before the Merkle
+ * fork. `difficulty_bits` of 0 means 4 bits per hex digit of `difficulty`.
+ */
+export class BlockTemplate {
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        BlockTemplateFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_blocktemplate_free(ptr, 0);
+    }
+    /**
+     * Leading zero bits a solution needs.
+     * @returns {number}
+     */
+    diff_bits() {
+        const ret = wasm.blocktemplate_diff_bits(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Block hash (hex) for `nonce` (BigInt).
+     * @param {bigint} nonce
+     * @returns {string}
+     */
+    finalize(nonce) {
+        let deferred2_0;
+        let deferred2_1;
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            wasm.blocktemplate_finalize(retptr, this.__wbg_ptr, addHeapObject(nonce));
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            var r3 = getDataViewMemory0().getInt32(retptr + 4 * 3, true);
+            var ptr1 = r0;
+            var len1 = r1;
+            if (r3) {
+                ptr1 = 0; len1 = 0;
+                throw takeObject(r2);
+            }
+            deferred2_0 = ptr1;
+            deferred2_1 = len1;
+            return getStringFromWasm0(ptr1, len1);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+            wasm.__wbindgen_export4(deferred2_0, deferred2_1, 1);
+        }
+    }
+    /**
+     * A ready-to-mine job for this template. Throws if its scheme puts
+     * more after the nonce than a job can hash.
+     * @returns {MiningJob}
+     */
+    mining_job() {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            wasm.blocktemplate_mining_job(retptr, this.__wbg_ptr);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            return MiningJob.__wrap(r0);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    /**
+     * `index` and `timestamp` are BigInts.
+     * @param {bigint} index
+     * @param {bigint} timestamp
+     * @param {string} tx_data
+     * @param {string} previous_hash
+     * @param {number} difficulty
+     * @param {number} difficulty_bits
+     */
+    constructor(index, timestamp, tx_data, previous_hash, difficulty, difficulty_bits) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            const ptr0 = passStringToWasm0(tx_data, wasm.__wbindgen_export, wasm.__wbindgen_export2);
+            const len0 = WASM_VECTOR_LEN;
+            const ptr1 = passStringToWasm0(previous_hash, wasm.__wbindgen_export, wasm.__wbindgen_export2);
+            const len1 = WASM_VECTOR_LEN;
+            wasm.blocktemplate_new(retptr, addHeapObject(index), addHeapObject(timestamp), ptr0, len0, ptr1, len1, difficulty, difficulty_bits);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            this.__wbg_ptr = r0 >>> 0;
+            BlockTemplateFinalization.register(this, this.__wbg_ptr, this);
+            return this;
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    /**
+     * @returns {PowScheme}
+     */
+    scheme() {
+        const ret = wasm.blocktemplate_scheme(this.__wbg_ptr);
+        return PowScheme.__wrap(ret);
+    }
+    /**
+     * Hash and mine under `scheme` instead of mainnet's. Jobs already
+     * made keep the old one.
+     * @param {PowScheme} scheme
+     */
+    set_scheme(scheme) {
+        _assertClass(scheme, PowScheme);
+        wasm.blocktemplate_set_scheme(this.__wbg_ptr, scheme.__wbg_ptr);
+    }
+    /**
+     * @returns {Target}
+     */
+    target() {
+        const ret = wasm.blocktemplate_target(this.__wbg_ptr);
+        return Target.__wrap(ret);
+    }
+    /**
+     * Is `hash_hex` the block hash for `nonce` (BigInt), and does it meet
+     * the target?
+     * @param {bigint} nonce
+     * @param {string} hash_hex
+     * @returns {boolean}
+     */
+    verify(nonce, hash_hex) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            const ptr0 = passStringToWasm0(hash_hex, wasm.__wbindgen_export, wasm.__wbindgen_export2);
+            const len0 = WASM_VECTOR_LEN;
+            wasm.blocktemplate_verify(retptr, this.__wbg_ptr, addHeapObject(nonce), ptr0, len0);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            return r0 !== 0;
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+}
+if (Symbol.dispose) BlockTemplate.prototype[Symbol.dispose] = BlockTemplate.prototype.free;
+
+/**
+ * Reusable mining job. Holds the midstate, tail and suffix in WASM
+ * memory so each batch only passes the nonce range.
+ *
+ * ```js
+ * const job = new MiningJob(prefixBytes, suffixBytes, diffBits);
+ * const hit = job.mine(workerId, numWorkers, BATCH_SIZE);
+ * const again = job.mine(job.next_nonce(), numWorkers, BATCH_SIZE);
+ * ```
+ */
+export class MiningJob {
+    static __wrap(ptr) {
+        ptr = ptr >>> 0;
+        const obj = Object.create(MiningJob.prototype);
+        obj.__wbg_ptr = ptr;
+        MiningJobFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        MiningJobFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_miningjob_free(ptr, 0);
+    }
+    /**
+     * Stats for the last `mine` / `mine_collect` call:
+     * { hashes, histogram, best }. `histogram[n]` counts hashes with `n`
+     * leading zero bits (the last entry: 32 or more); `best` is null or
+     * { nonce, hash, zeros, timestamp? }.
+     * @returns {any}
+     */
+    batch_stats() {
+        const ret = wasm.miningjob_batch_stats(this.__wbg_ptr);
+        return takeObject(ret);
+    }
+    /**
+     * Build a job from a `compute_midstate` result ({ h, len, tail }).
+     * @param {any} midstate
+     * @param {Uint8Array} suffix
+     * @param {number} diff_bits
+     * @returns {MiningJob}
+     */
+    static from_midstate(midstate, suffix, diff_bits) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            const ptr0 = passArray8ToWasm0(suffix, wasm.__wbindgen_export);
+            const len0 = WASM_VECTOR_LEN;
+            wasm.miningjob_from_midstate(retptr, addBorrowedObject(midstate), ptr0, len0, diff_bits);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            return MiningJob.__wrap(r0);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+            heap[stack_pointer++] = undefined;
+        }
+    }
+    /**
+     * Try `count` nonces `start, start + stride, ...` (BigInts). Returns
+     * null if no solution found, or { nonce: bigint, hash: string } on
+     * success, plus `timestamp` (bigint) for jobs from a `BlockTemplate`.
+     * Throws if the batch would leave 0..=i64::MAX.
+     * @param {bigint} start
+     * @param {bigint} stride
+     * @param {number} count
+     * @returns {any}
+     */
+    mine(start, stride, count) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            wasm.miningjob_mine(retptr, this.__wbg_ptr, addHeapObject(start), addHeapObject(stride), count);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            return takeObject(r0);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    /**
+     * `mine` with the current unix time in seconds (BigInt), so a job from
+     * a `BlockTemplate` can roll its timestamp once it is too old. After a
+     * roll the batch restarts from the job's first nonce, not `start`.
+     * @param {bigint} start
+     * @param {bigint} stride
+     * @param {number} count
+     * @param {bigint} now_secs
+     * @returns {any}
+     */
+    mine_at(start, stride, count, now_secs) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            wasm.miningjob_mine_at(retptr, this.__wbg_ptr, addHeapObject(start), addHeapObject(stride), count, addHeapObject(now_secs));
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            return takeObject(r0);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    /**
+     * Scan all `count` nonces and return every share:
+     * { shares: [{ nonce, hash, block, timestamp? }], scanned }. `block`
+     * marks hashes that also meet the `block` target. Stops early once
+     * `max_shares` are found; carry on from `next_nonce()`.
+     * @param {bigint} start
+     * @param {bigint} stride
+     * @param {number} count
+     * @param {Target} share
+     * @param {Target} block
+     * @param {number} max_shares
+     * @returns {any}
+     */
+    mine_collect(start, stride, count, share, block, max_shares) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            _assertClass(share, Target);
+            _assertClass(block, Target);
+            wasm.miningjob_mine_collect(retptr, this.__wbg_ptr, addHeapObject(start), addHeapObject(stride), count, share.__wbg_ptr, block.__wbg_ptr, max_shares);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            return takeObject(r0);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    /**
+     * Mine from `start` (BigInt) for about `duration_ms`, sizing batches
+     * from the measured hash rate instead of a fixed `BATCH_SIZE`. Returns
+     * { hashes, last_nonce: bigint | null, solution: null | { nonce, hash,
+     * timestamp? } }; carry on from `next_nonce()`. The clock is
+     * `Date.now()`, which also drives age-based timestamp rolling.
+     * @param {bigint} start
+     * @param {bigint} stride
+     * @param {number} duration_ms
+     * @returns {any}
+     */
+    mine_for(start, stride, duration_ms) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            wasm.miningjob_mine_for(retptr, this.__wbg_ptr, addHeapObject(start), addHeapObject(stride), duration_ms);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            return takeObject(r0);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    /**
+     * `mine`, but gives up early once `flag[0]` stops reading `generation`.
+     * `flag` is an `Int32Array` over a `SharedArrayBuffer` that the main
+     * thread bumps with `Atomics.store` on a new block; it is checked every
+     * few thousand nonces, so stale work stops within milliseconds.
+     * Returns { scanned, cancelled, solution: null | { nonce, hash,
+     * timestamp? } }; `next_nonce()` is just past the last nonce hashed.
+     * @param {bigint} start
+     * @param {bigint} stride
+     * @param {number} count
+     * @param {Int32Array} flag
+     * @param {number} generation
+     * @returns {any}
+     */
+    mine_until(start, stride, count, flag, generation) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            wasm.miningjob_mine_until(retptr, this.__wbg_ptr, addHeapObject(start), addHeapObject(stride), count, addBorrowedObject(flag), generation);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            return takeObject(r0);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+            heap[stack_pointer++] = undefined;
+        }
+    }
+    /**
+     * Build a job from the bytes before the nonce and after it. Throws
+     * if `suffix` is too long to mine.
+     * @param {Uint8Array} prefix
+     * @param {Uint8Array} suffix
+     * @param {number} diff_bits
+     */
+    constructor(prefix, suffix, diff_bits) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            const ptr0 = passArray8ToWasm0(prefix, wasm.__wbindgen_export);
+            const len0 = WASM_VECTOR_LEN;
+            const ptr1 = passArray8ToWasm0(suffix, wasm.__wbindgen_export);
+            const len1 = WASM_VECTOR_LEN;
+            wasm.miningjob_new(retptr, ptr0, len0, ptr1, len1, diff_bits);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            this.__wbg_ptr = r0 >>> 0;
+            MiningJobFinalization.register(this, this.__wbg_ptr, this);
+            return this;
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    /**
+     * Where the next `mine` call should start to carry on from the last.
+     * @returns {bigint}
+     */
+    next_nonce() {
+        const ret = wasm.miningjob_next_nonce(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Point the job at new work, reusing its buffers. Throws, leaving the
+     * job as it was, if `suffix` is too long to mine.
+     * @param {Uint8Array} prefix
+     * @param {Uint8Array} suffix
+     * @param {number} diff_bits
+     */
+    reset(prefix, suffix, diff_bits) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            const ptr0 = passArray8ToWasm0(prefix, wasm.__wbindgen_export);
+            const len0 = WASM_VECTOR_LEN;
+            const ptr1 = passArray8ToWasm0(suffix, wasm.__wbindgen_export);
+            const len1 = WASM_VECTOR_LEN;
+            wasm.miningjob_reset(retptr, this.__wbg_ptr, ptr0, len0, ptr1, len1, diff_bits);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            if (r1) {
+                throw takeObject(r0);
+            }
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    reset_stats() {
+        wasm.miningjob_reset_stats(this.__wbg_ptr);
+    }
+    /**
+     * Stats since the job was built or `reset_stats()`; survives `reset`.
+     * @returns {any}
+     */
+    session_stats() {
+        const ret = wasm.miningjob_session_stats(this.__wbg_ptr);
+        return takeObject(ret);
+    }
+    /**
+     * Roll the template timestamp after `max_nonces` (BigInt) nonces on
+     * it, or once it is `max_age_secs` behind the clock given to
+     * `mine_at`. 0 disables either limit.
+     * @param {bigint} max_nonces
+     * @param {number} max_age_secs
+     */
+    set_roll_policy(max_nonces, max_age_secs) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            wasm.miningjob_set_roll_policy(retptr, this.__wbg_ptr, addHeapObject(max_nonces), max_age_secs);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            if (r1) {
+                throw takeObject(r0);
+            }
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    /**
+     * Mine against `target` instead of whole leading zero bits. Kept until
+     * the next `reset`.
+     * @param {Target} target
+     */
+    set_target(target) {
+        _assertClass(target, Target);
+        wasm.miningjob_set_target(this.__wbg_ptr, target.__wbg_ptr);
+    }
+    /**
+     * @returns {Target}
+     */
+    target() {
+        const ret = wasm.miningjob_target(this.__wbg_ptr);
+        return Target.__wrap(ret);
+    }
+    /**
+     * Current template timestamp, or undefined for jobs without a template.
+     * @returns {bigint | undefined}
+     */
+    timestamp() {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            wasm.miningjob_timestamp(retptr, this.__wbg_ptr);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r2 = getDataViewMemory0().getBigInt64(retptr + 8 * 1, true);
+            return r0 === 0 ? undefined : r2;
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+}
+if (Symbol.dispose) MiningJob.prototype[Symbol.dispose] = MiningJob.prototype.free;
+
+/**
+ * How a block hash is computed: hash function, header field order and
+ * hashed difficulty. Mainnet's unless a testnet says otherwise.
+ *
+ * ```js
+ * const scheme = new PowScheme("sha256d:index,timestamp,previous_hash,tx_data,nonce,difficulty:bits");
+ * template.set_scheme(scheme);
+ * ```
+ */
+export class PowScheme {
+    static __wrap(ptr) {
+        ptr = ptr >>> 0;
+        const obj = Object.create(PowScheme.prototype);
+        obj.__wbg_ptr = ptr;
+        PowSchemeFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        PowSchemeFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_powscheme_free(ptr, 0);
+    }
+    /**
+     * @returns {PowScheme}
+     */
+    static mainnet() {
+        const ret = wasm.powscheme_mainnet();
+        return PowScheme.__wrap(ret);
+    }
+    /**
+     * Parse `hash:fields:difficulty`. Throws on a malformed scheme.
+     * @param {string} spec
+     */
+    constructor(spec) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            const ptr0 = passStringToWasm0(spec, wasm.__wbindgen_export, wasm.__wbindgen_export2);
+            const len0 = WASM_VECTOR_LEN;
+            wasm.powscheme_new(retptr, ptr0, len0);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            this.__wbg_ptr = r0 >>> 0;
+            PowSchemeFinalization.register(this, this.__wbg_ptr, this);
+            return this;
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    /**
+     * The `hash:fields:difficulty` form.
+     * @returns {string}
+     */
+    spec() {
+        let deferred1_0;
+        let deferred1_1;
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            wasm.powscheme_spec(retptr, this.__wbg_ptr);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            deferred1_0 = r0;
+            deferred1_1 = r1;
+            return getStringFromWasm0(r0, r1);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+            wasm.__wbindgen_export4(deferred1_0, deferred1_1, 1);
+        }
+    }
+}
+if (Symbol.dispose) PowScheme.prototype[Symbol.dispose] = PowScheme.prototype.free;
+
+/**
+ * 256-bit proof-of-work target; a hash meets it if it is numerically at
+ * or below it. Finer than leading zero bits, for pool vardiff.
+ */
+export class Target {
+    static __wrap(ptr) {
+        ptr = ptr >>> 0;
+        const obj = Object.create(Target.prototype);
+        obj.__wbg_ptr = ptr;
+        TargetFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        TargetFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_target_free(ptr, 0);
+    }
+    /**
+     * @returns {number}
+     */
+    difficulty() {
+        const ret = wasm.target_difficulty(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Target for `bits` leading zero bits.
+     * @param {number} bits
+     * @returns {Target}
+     */
+    static from_bits(bits) {
+        const ret = wasm.target_from_bits(bits);
+        return Target.__wrap(ret);
+    }
+    /**
+     * Target for a pool-style difficulty (`MAX / difficulty`). Throws
+     * unless the difficulty is a positive finite number.
+     * @param {number} difficulty
+     * @returns {Target}
+     */
+    static from_difficulty(difficulty) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            wasm.target_from_difficulty(retptr, difficulty);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            return Target.__wrap(r0);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    /**
+     * Parse 64 hex digits.
+     * @param {string} hex
+     * @returns {Target}
+     */
+    static from_hex(hex) {
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            const ptr0 = passStringToWasm0(hex, wasm.__wbindgen_export, wasm.__wbindgen_export2);
+            const len0 = WASM_VECTOR_LEN;
+            wasm.target_from_hex(retptr, ptr0, len0);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+            if (r2) {
+                throw takeObject(r1);
+            }
+            return Target.__wrap(r0);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+        }
+    }
+    /**
+     * Leading zero bits every hash meeting the target has.
+     * @returns {number}
+     */
+    leading_zeros() {
+        const ret = wasm.target_leading_zeros(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {string}
+     */
+    to_hex() {
+        let deferred1_0;
+        let deferred1_1;
+        try {
+            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+            wasm.target_to_hex(retptr, this.__wbg_ptr);
+            var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+            var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+            deferred1_0 = r0;
+            deferred1_1 = r1;
+            return getStringFromWasm0(r0, r1);
+        } finally {
+            wasm.__wbindgen_add_to_stack_pointer(16);
+            wasm.__wbindgen_export4(deferred1_0, deferred1_1, 1);
+        }
+    }
+}
+if (Symbol.dispose) Target.prototype[Symbol.dispose] = Target.prototype.free;
+
+/**
+ * Mine a typical block for about `duration_ms` on this thread through the
+ * real mining path. Returns { hashes, elapsed_ms, hashrate, backend },
+ * where `hashrate` is hashes/s. Timed with `performance.now()`, so call it
+ * from a worker or window; for a pool, run it in each worker and add up
+ * the hashrates.
+ * @param {number} duration_ms
+ * @returns {any}
+ */
+export function benchmark(duration_ms) {
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        wasm.benchmark(retptr, duration_ms);
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        if (r2) {
+            throw takeObject(r1);
+        }
+        return takeObject(r0);
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+    }
+}
+
+/**
+ * A block object's hash (hex) as the node computes it: over the Merkle
+ * root from the fork on, the transaction JSON before it.
+ * @param {any} block
+ * @returns {string}
+ */
+export function block_hash(block) {
+    let deferred2_0;
+    let deferred2_1;
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        wasm.block_hash(retptr, addBorrowedObject(block));
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        var r3 = getDataViewMemory0().getInt32(retptr + 4 * 3, true);
+        var ptr1 = r0;
+        var len1 = r1;
+        if (r3) {
+            ptr1 = 0; len1 = 0;
+            throw takeObject(r2);
+        }
+        deferred2_0 = ptr1;
+        deferred2_1 = len1;
+        return getStringFromWasm0(ptr1, len1);
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+        heap[stack_pointer++] = undefined;
+        wasm.__wbindgen_export4(deferred2_0, deferred2_1, 1);
+    }
+}
+
+/**
+ * The bytes a block object's hash is SHA-256 of, in the encoding its
+ * height calls for: the transaction JSON below the Merkle fork, the Merkle
+ * root from it on.
+ * @param {any} block
+ * @returns {Uint8Array}
+ */
+export function block_hash_preimage(block) {
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        wasm.block_hash_preimage(retptr, addBorrowedObject(block));
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        var r3 = getDataViewMemory0().getInt32(retptr + 4 * 3, true);
+        if (r3) {
+            throw takeObject(r2);
+        }
+        var v1 = getArrayU8FromWasm0(r0, r1).slice();
+        wasm.__wbindgen_export4(r0, r1 * 1, 1);
+        return v1;
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+        heap[stack_pointer++] = undefined;
+    }
+}
+
+/**
+ * `block_hash` for block JSON, e.g. straight from the node's API.
+ * @param {string} json
+ * @returns {string}
+ */
+export function block_json_hash(json) {
+    let deferred3_0;
+    let deferred3_1;
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        const ptr0 = passStringToWasm0(json, wasm.__wbindgen_export, wasm.__wbindgen_export2);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.block_json_hash(retptr, ptr0, len0);
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        var r3 = getDataViewMemory0().getInt32(retptr + 4 * 3, true);
+        var ptr2 = r0;
+        var len2 = r1;
+        if (r3) {
+            ptr2 = 0; len2 = 0;
+            throw takeObject(r2);
+        }
+        deferred3_0 = ptr2;
+        deferred3_1 = len2;
+        return getStringFromWasm0(ptr2, len2);
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+        wasm.__wbindgen_export4(deferred3_0, deferred3_1, 1);
+    }
+}
+
+/**
+ * `block_hash_preimage` for block JSON.
+ * @param {string} json
+ * @returns {Uint8Array}
+ */
+export function block_json_hash_preimage(json) {
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        const ptr0 = passStringToWasm0(json, wasm.__wbindgen_export, wasm.__wbindgen_export2);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.block_json_hash_preimage(retptr, ptr0, len0);
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        var r3 = getDataViewMemory0().getInt32(retptr + 4 * 3, true);
+        if (r3) {
+            throw takeObject(r2);
+        }
+        var v2 = getArrayU8FromWasm0(r0, r1).slice();
+        wasm.__wbindgen_export4(r0, r1 * 1, 1);
+        return v2;
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+    }
+}
+
+/**
+ * A block's JSON exactly as Go's json.Marshal writes it, for
+ * `/block/submit`. Throws on a malformed block.
+ * @param {any} block
+ * @returns {string}
+ */
+export function block_to_json(block) {
+    let deferred2_0;
+    let deferred2_1;
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        wasm.block_to_json(retptr, addBorrowedObject(block));
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        var r3 = getDataViewMemory0().getInt32(retptr + 4 * 3, true);
+        var ptr1 = r0;
+        var len1 = r1;
+        if (r3) {
+            ptr1 = 0; len1 = 0;
+            throw takeObject(r2);
+        }
+        deferred2_0 = ptr1;
+        deferred2_1 = len1;
+        return getStringFromWasm0(ptr1, len1);
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+        heap[stack_pointer++] = undefined;
+        wasm.__wbindgen_export4(deferred2_0, deferred2_1, 1);
+    }
+}
+
+/**
+ * Re-serialize block JSON from the node (or anywhere) canonically, so
+ * Nonces past 2^53 never pass through a JS number. Throws on bad JSON.
+ * @param {string} json
+ * @returns {string}
+ */
+export function canonical_block_json(json) {
+    let deferred3_0;
+    let deferred3_1;
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        const ptr0 = passStringToWasm0(json, wasm.__wbindgen_export, wasm.__wbindgen_export2);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.canonical_block_json(retptr, ptr0, len0);
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        var r3 = getDataViewMemory0().getInt32(retptr + 4 * 3, true);
+        var ptr2 = r0;
+        var len2 = r1;
+        if (r3) {
+            ptr2 = 0; len2 = 0;
+            throw takeObject(r2);
+        }
+        deferred3_0 = ptr2;
+        deferred3_1 = len2;
+        return getStringFromWasm0(ptr2, len2);
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+        wasm.__wbindgen_export4(deferred3_0, deferred3_1, 1);
+    }
+}
+
 /**
  * Check if a hex hash meets the required difficulty (leading zero bits).
  * @param {string} hash_hex
@@ -13,6 +891,20 @@ export function check_difficulty(hash_hex, diff_bits) {
     return ret !== 0;
 }
 
+/**
+ * Check if a hex hash is at or below `target`.
+ * @param {string} hash_hex
+ * @param {Target} target
+ * @returns {boolean}
+ */
+export function check_target(hash_hex, target) {
+    const ptr0 = passStringToWasm0(hash_hex, wasm.__wbindgen_export, wasm.__wbindgen_export2);
+    const len0 = WASM_VECTOR_LEN;
+    _assertClass(target, Target);
+    const ret = wasm.check_target(ptr0, len0, target.__wbg_ptr);
+    return ret !== 0;
+}
+
 /**
  * Compute the Merkle root of transaction JSON strings.
  * Input: newline-separated JSON strings (one per transaction).
@@ -39,6 +931,37 @@ export function compute_merkle_root(txs_newline_separated) {
     }
 }
 
+/**
+ * Merkle root (hex) of transaction objects, serialized as Go would.
+ * @param {Array<any>} txs
+ * @returns {string}
+ */
+export function compute_merkle_root_txs(txs) {
+    let deferred2_0;
+    let deferred2_1;
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        wasm.compute_merkle_root_txs(retptr, addBorrowedObject(txs));
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        var r3 = getDataViewMemory0().getInt32(retptr + 4 * 3, true);
+        var ptr1 = r0;
+        var len1 = r1;
+        if (r3) {
+            ptr1 = 0; len1 = 0;
+            throw takeObject(r2);
+        }
+        deferred2_0 = ptr1;
+        deferred2_1 = len1;
+        return getStringFromWasm0(ptr1, len1);
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+        heap[stack_pointer++] = undefined;
+        wasm.__wbindgen_export4(deferred2_0, deferred2_1, 1);
+    }
+}
+
 /**
  * Compute SHA-256 midstate for a prefix byte array.
  * Returns a JS object: { h: [u32 x 8], len: number, tail: Uint8Array }
@@ -56,40 +979,55 @@ export function compute_midstate(prefix) {
  * Compute the full block hash from components.
  * Matches Go's CalculateHash():
  *   SHA-256(index_str + timestamp_str + merkle_root + previous_hash + nonce_str + difficulty_str)
- * @param {number} index
- * @param {number} timestamp
+ *
+ * Below `MERKLE_ROOT_FORK_HEIGHT` pass the transaction JSON as
+ * `merkle_root`, or use `block_hash`, which picks by height.
+ *
+ * `index`, `timestamp` and `nonce` are BigInts (Go int64).
+ * @param {bigint} index
+ * @param {bigint} timestamp
  * @param {string} merkle_root
  * @param {string} previous_hash
- * @param {number} nonce
+ * @param {bigint} nonce
  * @param {number} difficulty
  * @returns {string}
  */
 export function hash_block(index, timestamp, merkle_root, previous_hash, nonce, difficulty) {
-    let deferred3_0;
-    let deferred3_1;
+    let deferred4_0;
+    let deferred4_1;
     try {
         const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
         const ptr0 = passStringToWasm0(merkle_root, wasm.__wbindgen_export, wasm.__wbindgen_export2);
         const len0 = WASM_VECTOR_LEN;
         const ptr1 = passStringToWasm0(previous_hash, wasm.__wbindgen_export, wasm.__wbindgen_export2);
         const len1 = WASM_VECTOR_LEN;
-        wasm.hash_block(retptr, index, timestamp, ptr0, len0, ptr1, len1, nonce, difficulty);
+        wasm.hash_block(retptr, addHeapObject(index), addHeapObject(timestamp), ptr0, len0, ptr1, len1, addHeapObject(nonce), difficulty);
         var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
         var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
-        deferred3_0 = r0;
-        deferred3_1 = r1;
-        return getStringFromWasm0(r0, r1);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        var r3 = getDataViewMemory0().getInt32(retptr + 4 * 3, true);
+        var ptr3 = r0;
+        var len3 = r1;
+        if (r3) {
+            ptr3 = 0; len3 = 0;
+            throw takeObject(r2);
+        }
+        deferred4_0 = ptr3;
+        deferred4_1 = len3;
+        return getStringFromWasm0(ptr3, len3);
     } finally {
         wasm.__wbindgen_add_to_stack_pointer(16);
-        wasm.__wbindgen_export4(deferred3_0, deferred3_1, 1);
+        wasm.__wbindgen_export4(deferred4_0, deferred4_1, 1);
     }
 }
 
 /**
  * Mine a batch of nonces. Returns null if no solution found,
- * or { nonce: number, hash: string } on success.
+ * or { nonce: bigint, hash: string } on success.
  *
- * Parameters match the Go worker's Mine() function.
+ * Parameters match the Go worker's Mine() function. `start_nonce` and
+ * `stride` are BigInts; throws if the batch would leave 0..=i64::MAX or
+ * `suffix` is too long to mine.
  * @param {number} h0
  * @param {number} h1
  * @param {number} h2
@@ -100,19 +1038,56 @@ export function hash_block(index, timestamp, merkle_root, previous_hash, nonce,
  * @param {number} h7
  * @param {Uint8Array} prefix_tail
  * @param {Uint8Array} suffix
- * @param {number} start_nonce
- * @param {number} stride
+ * @param {bigint} start_nonce
+ * @param {bigint} stride
  * @param {number} batch_size
  * @param {number} diff_bits
  * @param {number} midstate_len
  * @returns {any}
  */
 export function mine_batch(h0, h1, h2, h3, h4, h5, h6, h7, prefix_tail, suffix, start_nonce, stride, batch_size, diff_bits, midstate_len) {
-    const ptr0 = passArray8ToWasm0(prefix_tail, wasm.__wbindgen_export);
-    const len0 = WASM_VECTOR_LEN;
-    const ptr1 = passArray8ToWasm0(suffix, wasm.__wbindgen_export);
-    const len1 = WASM_VECTOR_LEN;
-    const ret = wasm.mine_batch(h0, h1, h2, h3, h4, h5, h6, h7, ptr0, len0, ptr1, len1, start_nonce, stride, batch_size, diff_bits, midstate_len);
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        const ptr0 = passArray8ToWasm0(prefix_tail, wasm.__wbindgen_export);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passArray8ToWasm0(suffix, wasm.__wbindgen_export);
+        const len1 = WASM_VECTOR_LEN;
+        wasm.mine_batch(retptr, h0, h1, h2, h3, h4, h5, h6, h7, ptr0, len0, ptr1, len1, addHeapObject(start_nonce), addHeapObject(stride), batch_size, diff_bits, midstate_len);
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        if (r2) {
+            throw takeObject(r1);
+        }
+        return takeObject(r0);
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+    }
+}
+
+export function reset_session_stats() {
+    wasm.reset_session_stats();
+}
+
+/**
+ * Run the known-answer vectors (SHA-256, genesis hash, midstate, and the
+ * genesis nonce through every backend's mining loop). Returns { ok,
+ * error: string | null }; don't mine when `ok` is false, as the engine
+ * would never find a valid block.
+ * @returns {any}
+ */
+export function self_test() {
+    const ret = wasm.self_test();
+    return takeObject(ret);
+}
+
+/**
+ * Hash stats across all `mine_batch` calls in this WASM instance:
+ * { hashes, histogram, best } as for `MiningJob.session_stats()`.
+ * @returns {any}
+ */
+export function session_stats() {
+    const ret = wasm.session_stats();
     return takeObject(ret);
 }
 
@@ -141,9 +1116,137 @@ export function sha256_hex(data) {
     }
 }
 
+/**
+ * A transaction's JSON exactly as Go's json.Marshal writes it: field
+ * order, omitempty for fee, data and public_key, and Go's `<`, `>`, `&`
+ * escaping. Throws on a malformed transaction.
+ * @param {any} tx
+ * @returns {string}
+ */
+export function transaction_to_json(tx) {
+    let deferred2_0;
+    let deferred2_1;
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        wasm.transaction_to_json(retptr, addBorrowedObject(tx));
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        var r3 = getDataViewMemory0().getInt32(retptr + 4 * 3, true);
+        var ptr1 = r0;
+        var len1 = r1;
+        if (r3) {
+            ptr1 = 0; len1 = 0;
+            throw takeObject(r2);
+        }
+        deferred2_0 = ptr1;
+        deferred2_1 = len1;
+        return getStringFromWasm0(ptr1, len1);
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+        heap[stack_pointer++] = undefined;
+        wasm.__wbindgen_export4(deferred2_0, deferred2_1, 1);
+    }
+}
+
+/**
+ * The JSON array of `txs`: the txData a pre-fork block hash commits to.
+ * @param {Array<any>} txs
+ * @returns {string}
+ */
+export function transactions_json(txs) {
+    let deferred2_0;
+    let deferred2_1;
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        wasm.transactions_json(retptr, addBorrowedObject(txs));
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        var r3 = getDataViewMemory0().getInt32(retptr + 4 * 3, true);
+        var ptr1 = r0;
+        var len1 = r1;
+        if (r3) {
+            ptr1 = 0; len1 = 0;
+            throw takeObject(r2);
+        }
+        deferred2_0 = ptr1;
+        deferred2_1 = len1;
+        return getStringFromWasm0(ptr1, len1);
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+        heap[stack_pointer++] = undefined;
+        wasm.__wbindgen_export4(deferred2_0, deferred2_1, 1);
+    }
+}
+
+/**
+ * Check a block object against consensus rules on top of `prev_header`
+ * ({ Index, Hash }): index and hash linkage, the recomputed hash and its
+ * difficulty, the Merkle root, one coinbase paying reward plus fees, and
+ * the minimum fee. `params` may be omitted for mainnet. Returns every
+ * broken rule (see `block_errors_to_js`); an empty array means valid.
+ * Throws only on a malformed argument.
+ * @param {any} block
+ * @param {any} prev_header
+ * @param {any} params
+ * @returns {Array<any>}
+ */
+export function validate_block(block, prev_header, params) {
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        wasm.validate_block(retptr, addBorrowedObject(block), addBorrowedObject(prev_header), addBorrowedObject(params));
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        if (r2) {
+            throw takeObject(r1);
+        }
+        return takeObject(r0);
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+        heap[stack_pointer++] = undefined;
+        heap[stack_pointer++] = undefined;
+        heap[stack_pointer++] = undefined;
+    }
+}
+
+/**
+ * `validate_block` for block JSON, e.g. straight from the node's API.
+ * @param {string} json
+ * @param {any} prev_header
+ * @param {any} params
+ * @returns {Array<any>}
+ */
+export function validate_block_json(json, prev_header, params) {
+    try {
+        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
+        const ptr0 = passStringToWasm0(json, wasm.__wbindgen_export, wasm.__wbindgen_export2);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.validate_block_json(retptr, ptr0, len0, addBorrowedObject(prev_header), addBorrowedObject(params));
+        var r0 = getDataViewMemory0().getInt32(retptr + 4 * 0, true);
+        var r1 = getDataViewMemory0().getInt32(retptr + 4 * 1, true);
+        var r2 = getDataViewMemory0().getInt32(retptr + 4 * 2, true);
+        if (r2) {
+            throw takeObject(r1);
+        }
+        return takeObject(r0);
+    } finally {
+        wasm.__wbindgen_add_to_stack_pointer(16);
+        heap[stack_pointer++] = undefined;
+        heap[stack_pointer++] = undefined;
+    }
+}
+
 function __wbg_get_imports() {
     const import0 = {
         __proto__: null,
+        __wbg___wbindgen_bigint_get_as_i64_578010f8442e0319: function(arg0, arg1) {
+            const v = getObject(arg1);
+            const ret = typeof(v) === 'bigint' ? v : undefined;
+            getDataViewMemory0().setBigInt64(arg0 + 8 * 1, isLikeNone(ret) ? BigInt(0) : ret, true);
+            getDataViewMemory0().setInt32(arg0 + 4 * 0, !isLikeNone(ret), true);
+        },
         __wbg___wbindgen_debug_string_ddde1867f49c2442: function(arg0, arg1) {
             const ret = debugString(getObject(arg1));
             const ptr1 = passStringToWasm0(ret, wasm.__wbindgen_export, wasm.__wbindgen_export2);
@@ -151,17 +1254,93 @@ function __wbg_get_imports() {
             getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
             getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
         },
+        __wbg___wbindgen_is_bigint_a157f0734ca85901: function(arg0) {
+            const ret = typeof(getObject(arg0)) === 'bigint';
+            return ret;
+        },
+        __wbg___wbindgen_is_null_a2a19127c13e7126: function(arg0) {
+            const ret = getObject(arg0) === null;
+            return ret;
+        },
+        __wbg___wbindgen_is_undefined_c18285b9fc34cb7d: function(arg0) {
+            const ret = getObject(arg0) === undefined;
+            return ret;
+        },
+        __wbg___wbindgen_jsval_eq_a6afb59d8c5e78d6: function(arg0, arg1) {
+            const ret = getObject(arg0) === getObject(arg1);
+            return ret;
+        },
+        __wbg___wbindgen_number_get_5854912275df1894: function(arg0, arg1) {
+            const obj = getObject(arg1);
+            const ret = typeof(obj) === 'number' ? obj : undefined;
+            getDataViewMemory0().setFloat64(arg0 + 8 * 1, isLikeNone(ret) ? 0 : ret, true);
+            getDataViewMemory0().setInt32(arg0 + 4 * 0, !isLikeNone(ret), true);
+        },
+        __wbg___wbindgen_string_get_3e5751597f39a112: function(arg0, arg1) {
+            const obj = getObject(arg1);
+            const ret = typeof(obj) === 'string' ? obj : undefined;
+            var ptr1 = isLikeNone(ret) ? 0 : passStringToWasm0(ret, wasm.__wbindgen_export, wasm.__wbindgen_export2);
+            var len1 = WASM_VECTOR_LEN;
+            getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+            getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+        },
         __wbg___wbindgen_throw_39bc967c0e5a9b58: function(arg0, arg1) {
             throw new Error(getStringFromWasm0(arg0, arg1));
         },
+        __wbg_get_18349afdb36339a9: function() { return handleError(function (arg0, arg1) {
+            const ret = Reflect.get(getObject(arg0), getObject(arg1));
+            return addHeapObject(ret);
+        }, arguments); },
+        __wbg_get_f09c3a16f8848381: function(arg0, arg1) {
+            const ret = getObject(arg0)[arg1 >>> 0];
+            return addHeapObject(ret);
+        },
+        __wbg_get_unchecked_3d0f4b91c8eca4f0: function(arg0, arg1) {
+            const ret = getObject(arg0)[arg1 >>> 0];
+            return addHeapObject(ret);
+        },
+        __wbg_instanceof_Performance_2e5967e4110be5c0: function(arg0) {
+            let result;
+            try {
+                result = getObject(arg0) instanceof Performance;
+            } catch (_) {
+                result = false;
+            }
+            const ret = result;
+            return ret;
+        },
+        __wbg_isArray_fad08a0d12828686: function(arg0) {
+            const ret = Array.isArray(getObject(arg0));
+            return ret;
+        },
         __wbg_length_5855c1f289dfffc1: function(arg0) {
             const ret = getObject(arg0).length;
             return ret;
         },
+        __wbg_length_a31e05262e09b7f8: function(arg0) {
+            const ret = getObject(arg0).length;
+            return ret;
+        },
+        __wbg_load_a4fe859f261ee199: function() { return handleError(function (arg0, arg1) {
+            const ret = Atomics.load(getObject(arg0), arg1 >>> 0);
+            return ret;
+        }, arguments); },
+        __wbg_new_09959f7b4c92c246: function(arg0) {
+            const ret = new Uint8Array(getObject(arg0));
+            return addHeapObject(ret);
+        },
+        __wbg_new_cbee8c0d5c479eac: function() {
+            const ret = new Array();
+            return addHeapObject(ret);
+        },
         __wbg_new_ed69e637b553a997: function() {
             const ret = new Object();
             return addHeapObject(ret);
         },
+        __wbg_new_typed_e8cd930b75161ad3: function() {
+            const ret = new Array();
+            return addHeapObject(ret);
+        },
         __wbg_new_with_length_51597651c65b2f13: function(arg0) {
             const ret = new Array(arg0 >>> 0);
             return addHeapObject(ret);
@@ -170,6 +1349,21 @@ function __wbg_get_imports() {
             const ret = new Uint8Array(arg0 >>> 0);
             return addHeapObject(ret);
         },
+        __wbg_now_b134ec02cd6d8b88: function(arg0) {
+            const ret = getObject(arg0).now();
+            return ret;
+        },
+        __wbg_now_edd718b3004d8631: function() {
+            const ret = Date.now();
+            return ret;
+        },
+        __wbg_prototypesetcall_f034d444741426c3: function(arg0, arg1, arg2) {
+            Uint8Array.prototype.set.call(getArrayU8FromWasm0(arg0, arg1), getObject(arg2));
+        },
+        __wbg_push_a6f9488ffd3fae3b: function(arg0, arg1) {
+            const ret = getObject(arg0).push(getObject(arg1));
+            return ret;
+        },
         __wbg_set_1fbeac6930844d01: function(arg0, arg1, arg2) {
             getObject(arg0).set(getArrayU8FromWasm0(arg1, arg2));
         },
@@ -180,16 +1374,46 @@ function __wbg_get_imports() {
             const ret = Reflect.set(getObject(arg0), getObject(arg1), getObject(arg2));
             return ret;
         }, arguments); },
+        __wbg_static_accessor_GLOBAL_THIS_14325d8cca34bb77: function() {
+            const ret = typeof globalThis === 'undefined' ? null : globalThis;
+            return isLikeNone(ret) ? 0 : addHeapObject(ret);
+        },
+        __wbg_static_accessor_GLOBAL_f3a1e69f9c5a7e8e: function() {
+            const ret = typeof global === 'undefined' ? null : global;
+            return isLikeNone(ret) ? 0 : addHeapObject(ret);
+        },
+        __wbg_static_accessor_SELF_50cdb5b517789aca: function() {
+            const ret = typeof self === 'undefined' ? null : self;
+            return isLikeNone(ret) ? 0 : addHeapObject(ret);
+        },
+        __wbg_static_accessor_WINDOW_d6c4126e4c244380: function() {
+            const ret = typeof window === 'undefined' ? null : window;
+            return isLikeNone(ret) ? 0 : addHeapObject(ret);
+        },
         __wbindgen_cast_0000000000000001: function(arg0) {
             // Cast intrinsic for `F64 -> Externref`.
             const ret = arg0;
             return addHeapObject(ret);
         },
-        __wbindgen_cast_0000000000000002: function(arg0, arg1) {
+        __wbindgen_cast_0000000000000002: function(arg0) {
+            // Cast intrinsic for `I64 -> Externref`.
+            const ret = arg0;
+            return addHeapObject(ret);
+        },
+        __wbindgen_cast_0000000000000003: function(arg0, arg1) {
             // Cast intrinsic for `Ref(String) -> Externref`.
             const ret = getStringFromWasm0(arg0, arg1);
             return addHeapObject(ret);
         },
+        __wbindgen_cast_0000000000000004: function(arg0) {
+            // Cast intrinsic for `U64 -> Externref`.
+            const ret = BigInt.asUintN(64, arg0);
+            return addHeapObject(ret);
+        },
+        __wbindgen_object_clone_ref: function(arg0) {
+            const ret = getObject(arg0);
+            return addHeapObject(ret);
+        },
         __wbindgen_object_drop_ref: function(arg0) {
             takeObject(arg0);
         },
@@ -200,6 +1424,19 @@ function __wbg_get_imports() {
     };
 }
 
+const BlockTemplateFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_blocktemplate_free(ptr >>> 0, 1));
+const MiningJobFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_miningjob_free(ptr >>> 0, 1));
+const PowSchemeFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_powscheme_free(ptr >>> 0, 1));
+const TargetFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_target_free(ptr >>> 0, 1));
+
 function addHeapObject(obj) {
     if (heap_next === heap.length) heap.push(heap.length + 1);
     const idx = heap_next;
@@ -209,6 +1446,18 @@ function addHeapObject(obj) {
     return idx;
 }
 
+function _assertClass(instance, klass) {
+    if (!(instance instanceof klass)) {
+        throw new Error(`expected instance of ${klass.name}`);
+    }
+}
+
+function addBorrowedObject(obj) {
+    if (stack_pointer == 1) throw new Error('out of js stack');
+    heap[--stack_pointer] = obj;
+    return stack_pointer;
+}
+
 function debugString(val) {
     // primitive types
     const type = typeof val;
@@ -321,6 +1570,10 @@ heap.push(undefined, null, true, false);
 
 let heap_next = heap.length;
 
+function isLikeNone(x) {
+    return x === undefined || x === null;
+}
+
 function passArray8ToWasm0(arg, malloc) {
     const ptr = malloc(arg.length * 1, 1) >>> 0;
     getUint8ArrayMemory0().set(arg, ptr / 1);
@@ -365,6 +1618,8 @@ function passStringToWasm0(arg, malloc, realloc) {
     return ptr;
 }
 
+let stack_pointer = 1024;
+
 function takeObject(idx) {
     const ret = getObject(idx);
     dropObject(idx);
@@ -0,0 +1,11 @@
+// Pool worker — runs one MinerPool thread of a threads build of the miner.
+// Instantiates the spawning module on its shared memory, so every thread
+// mines the same job in the same WASM memory; nothing to coordinate here.
+
+import { initSync, pool_worker_entry } from './dlt_webminer.js';
+
+self.onmessage = ({ data: [module, memory, ptr] }) => {
+    initSync({ module, memory });
+    pool_worker_entry(ptr);
+    close();
+};
@@ -44,7 +44,10 @@ function startMining(params) {
         midstateLen,   // number
     } = params;
 
-    let nonce = startNonce;
+    // Nonces cross into WASM as BigInt (i64) so they never lose precision
+    let nonce = BigInt(startNonce);
+    const strideBig = BigInt(stride);
+    const batchStep = strideBig * BigInt(BATCH_SIZE);
     let totalHashes = 0;
     let lastReport = performance.now();
 
@@ -56,14 +59,14 @@ function startMining(params) {
             prefixTail,
             suffix,
             nonce,
-            stride,
+            strideBig,
             BATCH_SIZE,
             diffBits,
             midstateLen
         );
 
         totalHashes += BATCH_SIZE;
-        nonce += stride * BATCH_SIZE;
+        nonce += batchStep;
 
         // Report hashrate every ~500ms
         const now = performance.now();
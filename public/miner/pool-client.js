@@ -1,6 +1,8 @@
 // Pool Client — WebSocket Stratum V1 client.
 // Connects to a WebSocket proxy that bridges to a Stratum TCP pool.
 
+import { stringifyInt64 } from './tx-builder.js';
+
 export class PoolClient {
     constructor(onWork, onDifficulty, onStats, onStatus) {
         this.ws = null;
@@ -135,7 +137,7 @@ export class PoolClient {
 
     _send(obj) {
         if (this.ws && this.ws.readyState === WebSocket.OPEN) {
-            this.ws.send(JSON.stringify(obj));
+            this.ws.send(stringifyInt64(obj));
         }
     }
 
@@ -68,6 +68,18 @@ export function buildCoinbaseTx(minerAddress, blockIndex, reward, totalFees) {
     };
 }
 
+// Placeholder for BigInt values while JSON.stringify runs; stripped after.
+const INT64_MARK = '\u0000int64:';
+
+/**
+ * JSON.stringify that writes BigInt values (nonces from the WASM miner) as
+ * bare integers, like Go's int64, instead of throwing.
+ */
+export function stringifyInt64(obj) {
+    return JSON.stringify(obj, (_, v) => typeof v === 'bigint' ? INT64_MARK + v : v)
+        .replace(/"\\u0000int64:(-?\d+)"/g, '$1');
+}
+
 /**
  * Serialize a block to JSON matching Go's json.Marshal.
  */
@@ -95,7 +107,7 @@ export function blockToJSON(block) {
     if (block.DifficultyBits && block.DifficultyBits !== 0) {
         obj.DifficultyBits = block.DifficultyBits;
     }
-    return JSON.stringify(obj);
+    return stringifyInt64(obj);
 }
 
 /**
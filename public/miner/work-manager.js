@@ -225,8 +225,8 @@ export class WorkManager {
 
         // Verify hash matches (sanity check)
         const expectedHash = this.wasm.hash_block(
-            this.currentBlock.Index,
-            this.currentBlock.Timestamp,
+            BigInt(this.currentBlock.Index),
+            BigInt(this.currentBlock.Timestamp),
             this.currentBlock.MerkleRoot,
             this.currentBlock.PreviousHash,
             nonce,